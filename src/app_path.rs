@@ -24,7 +24,7 @@ fn is_fault_file_name(file_name: &str) -> bool {
     if file_name == ".." {
         return true;
     };
-    if file_name == "" {
+    if file_name.is_empty() {
         return true;
     };
 
@@ -35,7 +35,7 @@ impl AbsFilePath {
     pub fn join_relative(&self, relative: &str) -> Result<Self, AbsFilePathError> {
         let mut splited: Vec<&str> = relative.split("/").collect();
 
-        let front = splited.get(0).expect("bug");
+        let front = splited.first().expect("bug");
         if front == &"" {
             return Err(AbsFilePathError::ExpectRelativePath);
         }
@@ -57,7 +57,7 @@ impl AbsFilePath {
                 continue;
             }
 
-            if dir_name == "" {
+            if dir_name.is_empty() {
                 continue;
             }
 
@@ -74,7 +74,7 @@ impl AbsFilePath {
         let mut splited: VecDeque<String> = abs_path.split("/").map(|x| x.to_string()).collect();
 
         let root = splited.pop_front().expect("bug");
-        if root != "" && root != "~" {
+        if !root.is_empty() && root != "~" {
             return Err(AbsFilePathError::ExpectAbsolutePath);
         }
 
@@ -84,11 +84,8 @@ impl AbsFilePath {
             };
             let mut splited_home: VecDeque<String> =
                 home_dir.split("/").map(|x| x.to_string()).collect();
-            loop {
-                let Some(dir_name) = splited_home.pop_back() else {
-                    break;
-                };
-                if dir_name == "" {
+            while let Some(dir_name) = splited_home.pop_back() {
+                if dir_name.is_empty() {
                     continue;
                 }
                 splited.push_front(dir_name);
@@ -108,13 +105,26 @@ impl AbsFilePath {
 
     pub fn get(&self) -> String {
         let mut jointed_dirs = self.dir.join(std::path::MAIN_SEPARATOR_STR);
-        if jointed_dirs == "" {
+        if jointed_dirs.is_empty() {
             jointed_dirs = "/".to_string();
         } else {
             jointed_dirs = format!("/{}/", jointed_dirs)
         }
         format!("{}{}", jointed_dirs, self.file_name)
     }
+
+    // エラーメッセージ用
+    // カレントディレクトリの中のファイルは相対パスで表示する
+    pub fn display(&self) -> String {
+        let path = self.get();
+        let Ok(current_dir) = std::env::current_dir() else {
+            return path;
+        };
+        match std::path::Path::new(&path).strip_prefix(current_dir) {
+            Ok(relative) => relative.to_string_lossy().to_string(),
+            Err(_) => path,
+        }
+    }
 }
 
 pub fn join_or_abs(abs: &AbsFilePath, join: &str) -> Result<AbsFilePath, AbsFilePathError> {
@@ -1,9 +1,6 @@
 fn pairwise_concat(chars: &mut dyn Iterator<Item = char>) -> Vec<String> {
     let mut ret_vec = Vec::new();
-    loop {
-        let Some(str1) = chars.next() else {
-            break;
-        };
+    while let Some(str1) = chars.next() {
         let Some(str2) = chars.next() else {
             break;
         };
@@ -15,58 +12,895 @@ fn pairwise_concat(chars: &mut dyn Iterator<Item = char>) -> Vec<String> {
 
 pub type ColorInt = u8;
 
+fn unit_to_channel(unit: f64) -> ColorInt {
+    (unit.clamp(0.0, 1.0) * 255.0).round() as ColorInt
+}
+
+// sRGBのガンマ補正を外す
+fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+type Vec3 = (f64, f64, f64);
+
+fn mul_matrix(m: &[[f64; 3]; 3], v: Vec3) -> Vec3 {
+    (
+        m[0][0] * v.0 + m[0][1] * v.1 + m[0][2] * v.2,
+        m[1][0] * v.0 + m[1][1] * v.1 + m[1][2] * v.2,
+        m[2][0] * v.0 + m[2][1] * v.1 + m[2][2] * v.2,
+    )
+}
+
+// https://bottosson.github.io/posts/oklab/
+const LINEAR_SRGB_TO_LMS: [[f64; 3]; 3] = [
+    [0.4122214708, 0.5363325363, 0.0514459929],
+    [0.2119034982, 0.6806995451, 0.1073969566],
+    [0.0883024619, 0.2817188376, 0.6299787005],
+];
+const LMS_TO_OKLAB: [[f64; 3]; 3] = [
+    [0.2104542553, 0.7936177850, -0.0040720468],
+    [1.9779984951, -2.4285922050, 0.4505937099],
+    [0.0259040371, 0.7827717662, -0.8086757660],
+];
+const OKLAB_TO_LMS: [[f64; 3]; 3] = [
+    [1.0, 0.3963377774, 0.2158037573],
+    [1.0, -0.1055613458, -0.0638541728],
+    [1.0, -0.0894841775, -1.2914855480],
+];
+const LMS_TO_LINEAR_SRGB: [[f64; 3]; 3] = [
+    [4.0767416621, -3.3077115913, 0.2309699292],
+    [-1.2684380046, 2.6097574011, -0.3413193965],
+    [-0.0041960863, -0.7034186147, 1.7076147010],
+];
+
+// https://www.w3.org/TR/css-color-4/#color-conversion-code
+// CSSのlab()に合わせて、Bradford変換でD50に順応させたXYZを使う
+const LINEAR_SRGB_TO_XYZ_D50: [[f64; 3]; 3] = [
+    [0.43606574282481125, 0.38515146883379114, 0.143078454422642],
+    [0.22249319175623722, 0.7168870538238821, 0.0606197905361654],
+    [
+        0.013923904500943501,
+        0.09708128566574632,
+        0.7140993584005154,
+    ],
+];
+const XYZ_D50_TO_LINEAR_SRGB: [[f64; 3]; 3] = [
+    [3.134135759892676, -1.6173856769662622, -0.4906624202927375],
+    [-0.978795455904033, 1.9162541885883537, 0.033442984116202286],
+    [0.07195541298945822, -0.22897667482785794, 1.405386011038788],
+];
+const D50_WHITE: Vec3 = (0.3457 / 0.3585, 1.0, (1.0 - 0.3457 - 0.3585) / 0.3585);
+
+fn linear_srgb_to_oklab(rgb: Vec3) -> Vec3 {
+    let (l, m, s) = mul_matrix(&LINEAR_SRGB_TO_LMS, rgb);
+    mul_matrix(&LMS_TO_OKLAB, (l.cbrt(), m.cbrt(), s.cbrt()))
+}
+
+fn oklab_to_linear_srgb(lab: Vec3) -> Vec3 {
+    let (l, m, s) = mul_matrix(&OKLAB_TO_LMS, lab);
+    mul_matrix(&LMS_TO_LINEAR_SRGB, (l.powi(3), m.powi(3), s.powi(3)))
+}
+
+fn linear_srgb_to_lab(rgb: Vec3) -> Vec3 {
+    const EPSILON: f64 = 216.0 / 24389.0;
+    const KAPPA: f64 = 24389.0 / 27.0;
+    let f = |t: f64| {
+        if t > EPSILON {
+            t.cbrt()
+        } else {
+            (KAPPA * t + 16.0) / 116.0
+        }
+    };
+
+    let (x, y, z) = mul_matrix(&LINEAR_SRGB_TO_XYZ_D50, rgb);
+    let (fx, fy, fz) = (f(x / D50_WHITE.0), f(y / D50_WHITE.1), f(z / D50_WHITE.2));
+
+    (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+}
+
+fn lab_to_linear_srgb(lab: Vec3) -> Vec3 {
+    const EPSILON: f64 = 216.0 / 24389.0;
+    const KAPPA: f64 = 24389.0 / 27.0;
+
+    let fy = (lab.0 + 16.0) / 116.0;
+    let fx = lab.1 / 500.0 + fy;
+    let fz = fy - lab.2 / 200.0;
+
+    let f_inv = |f: f64| {
+        if f.powi(3) > EPSILON {
+            f.powi(3)
+        } else {
+            (116.0 * f - 16.0) / KAPPA
+        }
+    };
+    let y = if lab.0 > KAPPA * EPSILON {
+        fy.powi(3)
+    } else {
+        lab.0 / KAPPA
+    };
+
+    let xyz = (
+        f_inv(fx) * D50_WHITE.0,
+        y * D50_WHITE.1,
+        f_inv(fz) * D50_WHITE.2,
+    );
+    mul_matrix(&XYZ_D50_TO_LINEAR_SRGB, xyz)
+}
+
+fn lab_to_lch(lab: Vec3) -> Vec3 {
+    let c = (lab.1 * lab.1 + lab.2 * lab.2).sqrt();
+    let h = normalize_hue(lab.2.atan2(lab.1).to_degrees());
+    (lab.0, c, h)
+}
+
+fn lch_to_lab(lch: Vec3) -> Vec3 {
+    let h = lch.2.to_radians();
+    (lch.0, lch.1 * h.cos(), lch.1 * h.sin())
+}
+
+fn in_srgb_gamut(rgb: Vec3) -> bool {
+    const E: f64 = 0.000_001;
+    [rgb.0, rgb.1, rgb.2]
+        .iter()
+        .all(|c| (-E..=1.0 + E).contains(c))
+}
+
+// sRGBの範囲外になる場合は、OKLCHの明度と色相を保ったまま彩度を下げる
+// 戻り値は線形sRGB
+fn gamut_map_oklch(lch: Vec3) -> Vec3 {
+    let (l, c, h) = lch;
+    if l >= 1.0 {
+        return (1.0, 1.0, 1.0);
+    }
+    if l <= 0.0 {
+        return (0.0, 0.0, 0.0);
+    }
+
+    let to_rgb = |c: f64| oklab_to_linear_srgb(lch_to_lab((l, c, h)));
+    let rgb = to_rgb(c);
+    if in_srgb_gamut(rgb) {
+        return rgb;
+    }
+
+    let mut low = 0.0;
+    let mut high = c;
+    while high - low > 0.0001 {
+        let mid = (low + high) / 2.0;
+        if in_srgb_gamut(to_rgb(mid)) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    to_rgb(low)
+}
+
+fn normalize_hue(h: f64) -> f64 {
+    h.rem_euclid(360.0)
+}
+
+// 彩度cと色相hから、最小値を0としたときのrgbを求める
+fn hue_to_unit_rgb(h: f64, c: f64) -> (f64, f64, f64) {
+    let h_dash = h / 60.0;
+    let x = c * (1.0 - (h_dash.rem_euclid(2.0) - 1.0).abs());
+
+    match h_dash as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    }
+}
+
+fn rgb_to_hue(r: f64, g: f64, b: f64, max: f64, c: f64) -> f64 {
+    if c == 0.0 {
+        return 0.0;
+    }
+
+    let h_dash = if max == r {
+        ((g - b) / c).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / c + 2.0
+    } else {
+        (r - g) / c + 4.0
+    };
+
+    normalize_hue(h_dash * 60.0)
+}
+
+pub const OPAQUE: ColorInt = 255;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendMode {
+    Multiply,
+    Screen,
+    Overlay,
+    SoftLight,
+    Difference,
+}
+
+impl BlendMode {
+    // cbは下の色(backdrop)、csは上に重ねる色(source) どちらも0から1
+    fn blend_channel(&self, cb: f64, cs: f64) -> f64 {
+        match self {
+            BlendMode::Multiply => cb * cs,
+            BlendMode::Screen => cb + cs - cb * cs,
+            BlendMode::Overlay => {
+                if cb <= 0.5 {
+                    2.0 * cb * cs
+                } else {
+                    BlendMode::Screen.blend_channel(2.0 * cb - 1.0, cs)
+                }
+            }
+            BlendMode::SoftLight => {
+                if cs <= 0.5 {
+                    cb - (1.0 - 2.0 * cs) * cb * (1.0 - cb)
+                } else {
+                    let d = if cb <= 0.25 {
+                        ((16.0 * cb - 12.0) * cb + 4.0) * cb
+                    } else {
+                        cb.sqrt()
+                    };
+                    cb + (2.0 * cs - 1.0) * (d - cb)
+                }
+            }
+            BlendMode::Difference => (cb - cs).abs(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Color {
     r: ColorInt,
     g: ColorInt,
     b: ColorInt,
+    a: ColorInt,
 }
 
 impl Color {
     pub fn new(r: ColorInt, g: ColorInt, b: ColorInt) -> Self {
-        Color { r, g, b }
+        Color { r, g, b, a: OPAQUE }
     }
 
-    pub fn plus(&self, r: ColorInt, g: ColorInt, b: ColorInt) -> Self {
-        let new_r = self.r.checked_add(r).unwrap_or(255);
-        let new_g = self.g.checked_add(g).unwrap_or(255);
-        let new_b = self.b.checked_add(b).unwrap_or(255);
+    pub fn new_rgba(r: ColorInt, g: ColorInt, b: ColorInt, a: ColorInt) -> Self {
+        Color { r, g, b, a }
+    }
+
+    pub fn with_alpha(&self, a: ColorInt) -> Self {
+        Self { a, ..self.clone() }
+    }
+
+    pub fn red(&self) -> ColorInt {
+        self.r
+    }
+    pub fn green(&self) -> ColorInt {
+        self.g
+    }
+    pub fn blue(&self) -> ColorInt {
+        self.b
+    }
+    pub fn alpha(&self) -> ColorInt {
+        self.a
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        self.a == OPAQUE
+    }
+
+    // 各チャンネルを0から1の範囲で返す
+    fn to_unit_rgb(&self) -> (f64, f64, f64) {
+        (
+            self.r as f64 / 255.0,
+            self.g as f64 / 255.0,
+            self.b as f64 / 255.0,
+        )
+    }
+
+    fn from_unit_rgb(r: f64, g: f64, b: f64) -> Self {
+        Color::new(unit_to_channel(r), unit_to_channel(g), unit_to_channel(b))
+    }
+
+    // hは角度(0以上360未満に正規化される)、sとlは0から1
+    pub fn from_hsl(h: f64, s: f64, l: f64) -> Self {
+        let h = normalize_hue(h);
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let m = l - c / 2.0;
+        let (r, g, b) = hue_to_unit_rgb(h, c);
+        Color::from_unit_rgb(r + m, g + m, b + m)
+    }
+
+    // (h, s, l) hは0以上360未満の角度、sとlは0から1
+    pub fn to_hsl(&self) -> (f64, f64, f64) {
+        let (r, g, b) = self.to_unit_rgb();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let c = max - min;
+        let l = (max + min) / 2.0;
+
+        let s = if c == 0.0 {
+            0.0
+        } else {
+            c / (1.0 - (2.0 * l - 1.0).abs())
+        };
+
+        (rgb_to_hue(r, g, b, max, c), s, l)
+    }
+
+    // hは角度、sとvは0から1
+    pub fn from_hsv(h: f64, s: f64, v: f64) -> Self {
+        let h = normalize_hue(h);
+        let c = v * s;
+        let m = v - c;
+        let (r, g, b) = hue_to_unit_rgb(h, c);
+        Color::from_unit_rgb(r + m, g + m, b + m)
+    }
+
+    // 以下はHSL空間で操作するため、色相が変わらない
+    // amountは0から1、アルファ値は引き継ぐ
+    pub fn lighten(&self, amount: f64) -> Self {
+        let (h, s, l) = self.to_hsl();
+        Color::from_hsl(h, s, (l + amount).clamp(0.0, 1.0)).with_alpha(self.a)
+    }
+    pub fn darken(&self, amount: f64) -> Self {
+        self.lighten(-amount)
+    }
+    pub fn saturate(&self, amount: f64) -> Self {
+        let (h, s, l) = self.to_hsl();
+        Color::from_hsl(h, (s + amount).clamp(0.0, 1.0), l).with_alpha(self.a)
+    }
+    pub fn desaturate(&self, amount: f64) -> Self {
+        self.saturate(-amount)
+    }
+    pub fn adjust_hue(&self, degrees: f64) -> Self {
+        let (h, s, l) = self.to_hsl();
+        Color::from_hsl(h + degrees, s, l).with_alpha(self.a)
+    }
+
+    // weightはselfの割合(0から1)、アルファ値も補間する
+    pub fn mix(&self, other: &Color, weight: f64) -> Self {
+        let lerp = |a: ColorInt, b: ColorInt| {
+            (a as f64 * weight + b as f64 * (1.0 - weight)).round() as ColorInt
+        };
 
+        Color {
+            r: lerp(self.r, other.r),
+            g: lerp(self.g, other.g),
+            b: lerp(self.b, other.b),
+            a: lerp(self.a, other.a),
+        }
+    }
+
+    // selfを下の色として、sourceを重ねる
+    // W3C Compositingと同じく、ブレンドした色を下の色のアルファ値でsourceと混ぜてからsource-overで合成する
+    pub fn blend(&self, source: &Color, mode: BlendMode) -> Self {
+        let ab = self.a as f64 / 255.0;
+        let as_ = source.a as f64 / 255.0;
+        let ao = as_ + ab * (1.0 - as_);
+        if ao == 0.0 {
+            return Color::new_rgba(0, 0, 0, 0);
+        }
+
+        let (br, bg, bb) = self.to_unit_rgb();
+        let (sr, sg, sb) = source.to_unit_rgb();
+        let composite = |cb: f64, cs: f64| {
+            let mixed = (1.0 - ab) * cs + ab * mode.blend_channel(cb, cs);
+            (mixed * as_ + cb * ab * (1.0 - as_)) / ao
+        };
+
+        Color::from_unit_rgb(composite(br, sr), composite(bg, sg), composite(bb, sb))
+            .with_alpha(unit_to_channel(ao))
+    }
+
+    // selfをbackgroundの上に重ねる(source-over合成)
+    pub fn over(&self, background: &Color) -> Self {
+        let af = self.a as f64 / 255.0;
+        let ab = background.a as f64 / 255.0;
+        let ao = af + ab * (1.0 - af);
+        if ao == 0.0 {
+            return Color::new_rgba(0, 0, 0, 0);
+        }
+
+        let (fr, fg, fb) = self.to_unit_rgb();
+        let (br, bg, bb) = background.to_unit_rgb();
+        let composite = |cf: f64, cb: f64| (cf * af + cb * ab * (1.0 - af)) / ao;
+
+        Color::from_unit_rgb(composite(fr, br), composite(fg, bg), composite(fb, bb))
+            .with_alpha(unit_to_channel(ao))
+    }
+
+    fn to_linear_rgb(&self) -> Vec3 {
+        let (r, g, b) = self.to_unit_rgb();
+        (srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b))
+    }
+
+    fn from_linear_rgb(rgb: Vec3) -> Self {
+        Color::from_unit_rgb(
+            linear_to_srgb(rgb.0),
+            linear_to_srgb(rgb.1),
+            linear_to_srgb(rgb.2),
+        )
+    }
+
+    // lは0から1、aとbはおおよそ-0.4から0.4
+    pub fn from_oklab(l: f64, a: f64, b: f64) -> Self {
+        Color::from_oklch(lab_to_lch((l, a, b)))
+    }
+    pub fn to_oklab(&self) -> Vec3 {
+        linear_srgb_to_oklab(self.to_linear_rgb())
+    }
+
+    // (l, c, h) lは0から1、cはおおよそ0から0.4、hは角度
+    pub fn from_oklch(lch: Vec3) -> Self {
+        Color::from_linear_rgb(gamut_map_oklch(lch))
+    }
+    pub fn to_oklch(&self) -> Vec3 {
+        lab_to_lch(self.to_oklab())
+    }
+
+    // CIELAB(D50) lは0から100、aとbはおおよそ-125から125
+    pub fn from_lab(l: f64, a: f64, b: f64) -> Self {
+        let rgb = lab_to_linear_srgb((l, a, b));
+        if in_srgb_gamut(rgb) {
+            return Color::from_linear_rgb(rgb);
+        }
+        Color::from_oklch(lab_to_lch(linear_srgb_to_oklab(rgb)))
+    }
+    pub fn to_lab(&self) -> Vec3 {
+        linear_srgb_to_lab(self.to_linear_rgb())
+    }
+
+    // WCAG 2の相対輝度 0から1 アルファ値は無視する
+    pub fn luminance(&self) -> f64 {
+        let (r, g, b) = self.to_linear_rgb();
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+
+    // WCAG 2のコントラスト比 1から21
+    pub fn contrast(&self, other: &Color) -> f64 {
+        let l1 = self.luminance();
+        let l2 = other.luminance();
+        (l1.max(l2) + 0.05) / (l1.min(l2) + 0.05)
+    }
+
+    // パレットの段階(0から1000)に応じた色を作る
+    // 500がself、0に向かって白、1000に向かって黒に近づく
+    // OKLCHの明度を補間するので、段階ごとの明るさの差が知覚的に均等になる
+    pub fn ramp_step(&self, step: f64) -> Self {
+        const LIGHTEST: f64 = 0.98;
+        const DARKEST: f64 = 0.2;
+
+        let (l, c, h) = self.to_oklch();
+        let step = step.clamp(0.0, 1000.0);
+
+        let new_l = if step <= 500.0 {
+            let t = step / 500.0;
+            LIGHTEST + (l - LIGHTEST) * t
+        } else {
+            let t = (step - 500.0) / 500.0;
+            l + (DARKEST - l) * t
+        };
+
+        Color::from_oklch((new_l, c, h)).with_alpha(self.a)
+    }
+
+    // (h, s, v)
+    pub fn to_hsv(&self) -> (f64, f64, f64) {
+        let (r, g, b) = self.to_unit_rgb();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let c = max - min;
+
+        let s = if max == 0.0 { 0.0 } else { c / max };
+
+        (rgb_to_hue(r, g, b, max, c), s, max)
+    }
+
+    // アルファ値はそのまま引き継ぐ
+    pub fn plus(&self, r: ColorInt, g: ColorInt, b: ColorInt) -> Self {
         Self {
-            r: new_r,
-            g: new_g,
-            b: new_b,
+            r: self.r.saturating_add(r),
+            g: self.g.saturating_add(g),
+            b: self.b.saturating_add(b),
+            a: self.a,
         }
     }
     pub fn minus(&self, r: ColorInt, g: ColorInt, b: ColorInt) -> Self {
-        let new_r = self.r.checked_sub(r).unwrap_or(0);
-        let new_g = self.g.checked_sub(g).unwrap_or(0);
-        let new_b = self.b.checked_sub(b).unwrap_or(0);
+        Self {
+            r: self.r.saturating_sub(r),
+            g: self.g.saturating_sub(g),
+            b: self.b.saturating_sub(b),
+            a: self.a,
+        }
+    }
+
+    pub fn plus_alpha(&self, a: ColorInt) -> Self {
+        Self {
+            a: self.a.saturating_add(a),
+            ..self.clone()
+        }
+    }
+    pub fn minus_alpha(&self, a: ColorInt) -> Self {
+        Self {
+            a: self.a.saturating_sub(a),
+            ..self.clone()
+        }
+    }
 
+    // 演算子用 RGBのチャンネルごとに計算して0から255に丸める
+    // アルファ値はそのまま引き継ぐ
+    pub fn zip_channels(&self, other: &Color, f: impl Fn(f64, f64) -> f64) -> Self {
+        let channel =
+            |a: ColorInt, b: ColorInt| f(a as f64, b as f64).round().clamp(0.0, 255.0) as ColorInt;
         Self {
-            r: new_r,
-            g: new_g,
-            b: new_b,
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+            a: self.a,
         }
     }
 
+    pub fn map_channels(&self, f: impl Fn(f64) -> f64) -> Self {
+        self.zip_channels(self, |c, _| f(c))
+    }
+
+    // `rgb` `rgba` `rrggbb` `rrggbbaa` のいずれかの形式を受け付ける
     pub fn from_hex_chars(chars: &mut dyn Iterator<Item = char>) -> Option<Self> {
-        let two_chars = pairwise_concat(chars);
+        let chars: Vec<char> = chars.collect();
 
-        let Ok(r) = u8::from_str_radix(two_chars[0].as_str(), 16) else {
-            return None;
-        };
-        let Ok(g) = u8::from_str_radix(two_chars[1].as_str(), 16) else {
-            return None;
-        };
-        let Ok(b) = u8::from_str_radix(two_chars[2].as_str(), 16) else {
-            return None;
+        let expanded: Vec<char> = match chars.len() {
+            3 | 4 => chars.iter().flat_map(|&ch| [ch, ch]).collect(),
+            6 | 8 => chars,
+            _ => return None,
         };
 
-        Some(Color { r, g, b })
+        let mut channels = Vec::with_capacity(4);
+        for two_chars in pairwise_concat(&mut expanded.into_iter()) {
+            let Ok(channel) = u8::from_str_radix(two_chars.as_str(), 16) else {
+                return None;
+            };
+            channels.push(channel);
+        }
+
+        let a = channels.get(3).copied().unwrap_or(OPAQUE);
+
+        Some(Color {
+            r: channels[0],
+            g: channels[1],
+            b: channels[2],
+            a,
+        })
     }
 
+    // 不透明な場合は`#rrggbb`、そうでなければ`#rrggbbaa`
     pub fn to_hex_string(&self) -> String {
-        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        if self.is_opaque() {
+            format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BlendMode, Color};
+
+    fn from_hex(hex: &str) -> Option<Color> {
+        Color::from_hex_chars(&mut hex.chars())
+    }
+
+    #[test]
+    fn from_hex_chars() {
+        assert_eq!(from_hex("ffffff"), Some(Color::new(255, 255, 255)));
+        assert_eq!(from_hex("102030"), Some(Color::new(16, 32, 48)));
+        assert_eq!(from_hex("10203040"), Some(Color::new_rgba(16, 32, 48, 64)));
+        assert_eq!(from_hex("abc"), Some(Color::new(0xaa, 0xbb, 0xcc)));
+        assert_eq!(
+            from_hex("abcd"),
+            Some(Color::new_rgba(0xaa, 0xbb, 0xcc, 0xdd))
+        );
+
+        assert_eq!(from_hex(""), None);
+        assert_eq!(from_hex("ff"), None);
+        assert_eq!(from_hex("fffff"), None);
+        assert_eq!(from_hex("fffffff"), None);
+        assert_eq!(from_hex("fffffffff"), None);
+        assert_eq!(from_hex("gggggg"), None);
+    }
+
+    #[test]
+    fn to_hex_string() {
+        assert_eq!(Color::new(16, 32, 48).to_hex_string(), "#102030");
+        assert_eq!(Color::new_rgba(16, 32, 48, 255).to_hex_string(), "#102030");
+        assert_eq!(Color::new_rgba(16, 32, 48, 0).to_hex_string(), "#10203000");
+        assert_eq!(
+            Color::new_rgba(16, 32, 48, 128).to_hex_string(),
+            "#10203080"
+        );
+    }
+
+    fn assert_close(a: (f64, f64, f64), b: (f64, f64, f64)) {
+        let close =
+            (a.0 - b.0).abs() < 0.5 && (a.1 - b.1).abs() < 0.005 && (a.2 - b.2).abs() < 0.005;
+        assert!(close, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn hsl() {
+        assert_eq!(Color::from_hsl(0.0, 1.0, 0.5), Color::new(255, 0, 0));
+        assert_eq!(Color::from_hsl(120.0, 1.0, 0.5), Color::new(0, 255, 0));
+        assert_eq!(Color::from_hsl(240.0, 1.0, 0.5), Color::new(0, 0, 255));
+        assert_eq!(Color::from_hsl(600.0, 1.0, 0.5), Color::new(0, 0, 255));
+        assert_eq!(Color::from_hsl(-120.0, 1.0, 0.5), Color::new(0, 0, 255));
+        assert_eq!(Color::from_hsl(0.0, 0.0, 1.0), Color::new(255, 255, 255));
+        assert_eq!(Color::from_hsl(0.0, 0.0, 0.0), Color::new(0, 0, 0));
+        assert_eq!(Color::from_hsl(210.0, 0.5, 0.4), Color::new(51, 102, 153));
+
+        assert_close(Color::new(255, 0, 0).to_hsl(), (0.0, 1.0, 0.5));
+        assert_close(Color::new(51, 102, 153).to_hsl(), (210.0, 0.5, 0.4));
+        assert_close(Color::new(128, 128, 128).to_hsl(), (0.0, 0.0, 0.502));
+        assert_close(Color::new(255, 0, 128).to_hsl(), (329.9, 1.0, 0.5));
+    }
+
+    #[test]
+    fn hsv() {
+        assert_eq!(Color::from_hsv(0.0, 1.0, 1.0), Color::new(255, 0, 0));
+        assert_eq!(Color::from_hsv(60.0, 1.0, 1.0), Color::new(255, 255, 0));
+        assert_eq!(Color::from_hsv(210.0, 0.5, 0.6), Color::new(77, 115, 153));
+        assert_eq!(Color::from_hsv(0.0, 0.0, 0.0), Color::new(0, 0, 0));
+
+        assert_close(Color::new(255, 255, 0).to_hsv(), (60.0, 1.0, 1.0));
+        assert_close(Color::new(77, 115, 153).to_hsv(), (210.0, 0.497, 0.6));
+        assert_close(Color::new(0, 0, 0).to_hsv(), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn hsl_adjustment() {
+        let color = Color::new_rgba(51, 102, 153, 100);
+        assert_eq!(color.lighten(0.2), Color::new_rgba(102, 153, 204, 100));
+        assert_eq!(color.darken(0.2), Color::new_rgba(26, 51, 77, 100));
+        assert_eq!(color.darken(1.0), Color::new_rgba(0, 0, 0, 100));
+        assert_eq!(color.saturate(0.5), Color::new_rgba(0, 102, 204, 100));
+        assert_eq!(color.desaturate(1.0), Color::new_rgba(102, 102, 102, 100));
+        assert_eq!(color.adjust_hue(180.0), Color::new_rgba(153, 102, 51, 100));
+        assert_eq!(color.adjust_hue(-360.0), color);
+
+        // RGBの加算と違い、明るくしても色相が変わらない
+        let (h, _, _) = Color::new(255, 100, 0).lighten(0.3).to_hsl();
+        assert!((h - Color::new(255, 100, 0).to_hsl().0).abs() < 1.0);
+    }
+
+    #[test]
+    fn mix() {
+        let black = Color::new(0, 0, 0);
+        let white = Color::new(255, 255, 255);
+        assert_eq!(black.mix(&white, 0.5), Color::new(128, 128, 128));
+        assert_eq!(black.mix(&white, 1.0), black);
+        assert_eq!(black.mix(&white, 0.0), white);
+        assert_eq!(
+            Color::new_rgba(100, 0, 0, 0).mix(&Color::new(0, 100, 0), 0.25),
+            Color::new_rgba(25, 75, 0, 191)
+        );
+    }
+
+    #[test]
+    fn blend() {
+        let base = Color::new(200, 100, 50);
+        let gray = Color::new(128, 128, 128);
+        let white = Color::new(255, 255, 255);
+
+        assert_eq!(
+            base.blend(&white, BlendMode::Multiply),
+            Color::new(200, 100, 50)
+        );
+        assert_eq!(
+            base.blend(&gray, BlendMode::Multiply),
+            Color::new(100, 50, 25)
+        );
+        assert_eq!(base.blend(&white, BlendMode::Screen), white);
+        assert_eq!(
+            base.blend(&gray, BlendMode::Screen),
+            Color::new(228, 178, 153)
+        );
+        assert_eq!(
+            base.blend(&gray, BlendMode::Overlay),
+            Color::new(200, 100, 50)
+        );
+        assert_eq!(
+            base.blend(&white, BlendMode::SoftLight),
+            Color::new(226, 160, 113)
+        );
+        assert_eq!(
+            base.blend(&gray, BlendMode::SoftLight),
+            Color::new(200, 100, 50)
+        );
+        assert_eq!(
+            base.blend(&white, BlendMode::Difference),
+            Color::new(55, 155, 205)
+        );
+
+        // 半透明の色は下の色と混ざる
+        let black = Color::new_rgba(0, 0, 0, 128);
+        assert_eq!(
+            base.blend(&black, BlendMode::Multiply),
+            Color::new(100, 50, 25)
+        );
+        assert_eq!(
+            base.blend(&Color::new_rgba(0, 0, 0, 0), BlendMode::Multiply),
+            base
+        );
+        // 下の色が半透明ならその分だけ上の色が残る
+        assert_eq!(
+            Color::new_rgba(200, 100, 50, 0).blend(&gray, BlendMode::Multiply),
+            gray
+        );
+        assert_eq!(
+            Color::new_rgba(0, 0, 0, 0).blend(&Color::new_rgba(0, 0, 0, 0), BlendMode::Screen),
+            Color::new_rgba(0, 0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn over() {
+        let bg = Color::new(255, 255, 255);
+        assert_eq!(Color::new(0, 0, 0).over(&bg), Color::new(0, 0, 0));
+        assert_eq!(Color::new_rgba(0, 0, 0, 0).over(&bg), bg);
+        assert_eq!(
+            Color::new_rgba(0, 0, 0, 128).over(&bg),
+            Color::new(127, 127, 127)
+        );
+        assert_eq!(
+            Color::new_rgba(255, 0, 0, 128).over(&Color::new_rgba(0, 0, 255, 128)),
+            Color::new_rgba(170, 0, 85, 192)
+        );
+        assert_eq!(
+            Color::new_rgba(0, 0, 0, 0).over(&Color::new_rgba(0, 0, 0, 0)),
+            Color::new_rgba(0, 0, 0, 0)
+        );
+    }
+
+    fn assert_close_vec3(a: (f64, f64, f64), b: (f64, f64, f64), epsilon: f64) {
+        let close = (a.0 - b.0).abs() < epsilon
+            && (a.1 - b.1).abs() < epsilon
+            && (a.2 - b.2).abs() < epsilon;
+        assert!(close, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn oklab() {
+        assert_close_vec3(Color::new(255, 255, 255).to_oklab(), (1.0, 0.0, 0.0), 0.001);
+        assert_close_vec3(Color::new(0, 0, 0).to_oklab(), (0.0, 0.0, 0.0), 0.001);
+        assert_close_vec3(
+            Color::new(255, 0, 0).to_oklab(),
+            (0.628, 0.2249, 0.1258),
+            0.001,
+        );
+        assert_close_vec3(
+            Color::new(255, 0, 0).to_oklch(),
+            (0.628, 0.2577, 29.23),
+            0.01,
+        );
+
+        assert_eq!(Color::from_oklab(1.0, 0.0, 0.0), Color::new(255, 255, 255));
+        assert_eq!(
+            Color::from_oklab(0.628, 0.2249, 0.1258),
+            Color::new(255, 0, 0)
+        );
+        assert_eq!(
+            Color::from_oklch((0.628, 0.2577, 29.23)),
+            Color::new(255, 0, 0)
+        );
+
+        let color = Color::new(51, 102, 153);
+        let (l, a, b) = color.to_oklab();
+        assert_eq!(Color::from_oklab(l, a, b), color);
+        assert_eq!(Color::from_oklch(color.to_oklch()), color);
+    }
+
+    #[test]
+    fn lab() {
+        assert_close_vec3(Color::new(255, 255, 255).to_lab(), (100.0, 0.0, 0.0), 0.01);
+        assert_close_vec3(Color::new(255, 0, 0).to_lab(), (54.29, 80.80, 69.89), 0.05);
+        assert_eq!(Color::from_lab(54.29, 80.80, 69.89), Color::new(255, 0, 0));
+        assert_eq!(Color::from_lab(0.0, 0.0, 0.0), Color::new(0, 0, 0));
+
+        let color = Color::new(51, 102, 153);
+        let (l, a, b) = color.to_lab();
+        assert_eq!(Color::from_lab(l, a, b), color);
+    }
+
+    #[test]
+    fn gamut_mapping() {
+        // sRGBの範囲外でも明度と色相を保ったまま範囲内に収める
+        let mapped = Color::from_oklch((0.7, 0.4, 150.0));
+        let (l, c, h) = mapped.to_oklch();
+        assert!((l - 0.7).abs() < 0.01, "{}", l);
+        assert!((h - 150.0).abs() < 1.0, "{}", h);
+        assert!(c < 0.4, "{}", c);
+
+        let (_, a, b) = Color::from_lab(50.0, 150.0, -150.0).to_lab();
+        assert!(a > 0.0 && b < 0.0, "{} {}", a, b);
+
+        assert_eq!(
+            Color::from_oklch((1.2, 0.3, 10.0)),
+            Color::new(255, 255, 255)
+        );
+        assert_eq!(Color::from_oklch((-0.1, 0.3, 10.0)), Color::new(0, 0, 0));
+    }
+
+    #[test]
+    fn contrast() {
+        let black = Color::new(0, 0, 0);
+        let white = Color::new(255, 255, 255);
+        assert_eq!(black.luminance(), 0.0);
+        assert_eq!(white.luminance(), 1.0);
+        assert_eq!(black.contrast(&white), 21.0);
+        assert_eq!(white.contrast(&black), 21.0);
+        assert_eq!(white.contrast(&white), 1.0);
+
+        let gray = Color::new(0x76, 0x76, 0x76);
+        assert!((gray.contrast(&white) - 4.54).abs() < 0.01);
+        assert!((Color::new(255, 0, 0).luminance() - 0.2126).abs() < 0.0001);
+    }
+
+    #[test]
+    fn ramp_step() {
+        let base = Color::new_rgba(51, 102, 204, 200);
+        assert_eq!(base.ramp_step(500.0), base);
+
+        let lightness: Vec<f64> = [0.0, 100.0, 300.0, 500.0, 700.0, 900.0, 1000.0]
+            .iter()
+            .map(|&step| base.ramp_step(step).to_oklch().0)
+            .collect();
+        for pair in lightness.windows(2) {
+            assert!(pair[0] > pair[1], "{:?}", lightness);
+        }
+        assert!((lightness[0] - 0.98).abs() < 0.01);
+        assert!((lightness[6] - 0.2).abs() < 0.01);
+
+        // 色相は保たれる
+        let (_, _, h) = base.ramp_step(200.0).to_oklch();
+        assert!((h - base.to_oklch().2).abs() < 2.0);
+        assert_eq!(base.ramp_step(900.0).alpha(), 200);
+    }
+
+    #[test]
+    fn plus_minus_keep_alpha() {
+        let color = Color::new_rgba(250, 10, 100, 128);
+        assert_eq!(color.plus(10, 10, 10), Color::new_rgba(255, 20, 110, 128));
+        assert_eq!(color.minus(10, 20, 10), Color::new_rgba(240, 0, 90, 128));
+        assert_eq!(color.plus_alpha(200), Color::new_rgba(250, 10, 100, 255));
+        assert_eq!(color.minus_alpha(200), Color::new_rgba(250, 10, 100, 0));
+    }
+
+    #[test]
+    fn channel_arithmetic() {
+        let color = Color::new_rgba(100, 200, 250, 128);
+        assert_eq!(
+            color.map_channels(|c| c * 0.5),
+            Color::new_rgba(50, 100, 125, 128)
+        );
+        assert_eq!(
+            color.map_channels(|c| c + 10.0),
+            Color::new_rgba(110, 210, 255, 128)
+        );
+        assert_eq!(
+            color.zip_channels(&Color::new(200, 10, 0), |a, b| a - b),
+            Color::new_rgba(0, 190, 250, 128)
+        );
     }
 }
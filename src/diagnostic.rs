@@ -0,0 +1,222 @@
+// Faultをrustcのような形式で表示する
+
+use std::io::IsTerminal;
+
+use clap::ValueEnum;
+use serde_json::json;
+
+use crate::{fault::Fault, location::Location};
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    // autoの場合はstderrが端末で、NO_COLORが設定されていないときだけ色をつける
+    pub fn enabled(&self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ErrorFormat {
+    // ソースコードの行と^で場所を示す
+    Human,
+    // `file:line:col: msg` の1行
+    Short,
+    // 1行に1つのJSONオブジェクト
+    Json,
+}
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+struct Painter {
+    color: bool,
+}
+
+impl Painter {
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+// sourceはfaultが起きたファイルの中身
+pub fn render(fault: &dyn Fault, format: ErrorFormat, color: bool, source: Option<&str>) -> String {
+    match format {
+        ErrorFormat::Human => render_human(fault, &Painter { color }, source),
+        ErrorFormat::Short => fault.report(),
+        ErrorFormat::Json => render_json(fault),
+    }
+}
+
+fn render_human(fault: &dyn Fault, painter: &Painter, source: Option<&str>) -> String {
+    let mut lines = vec![painter.paint(RED, &fault.msg())];
+
+    let mut gutter_width = 1;
+    if let Some(location) = fault.location() {
+        let line_number = location.span.start.line.to_string();
+        gutter_width = line_number.len() + 1;
+        let gutter = " ".repeat(gutter_width);
+
+        lines.push(format!(
+            "{}{}",
+            painter.paint(BLUE, &format!("{}--> ", &gutter[1..])),
+            location
+        ));
+
+        let source_line =
+            source.and_then(|source| source.lines().nth(location.span.start.line - 1));
+        if let Some(source_line) = source_line {
+            let bar = painter.paint(BLUE, &format!("{}|", gutter));
+            lines.push(bar.clone());
+            lines.push(format!(
+                "{} {}",
+                painter.paint(BLUE, &format!("{} |", line_number)),
+                source_line
+            ));
+
+            let mut marker = underline(location, source_line);
+            if let Some(label) = fault.label() {
+                marker = format!("{} {}", marker, label);
+            }
+            lines.push(format!(
+                "{} {}{}",
+                bar,
+                indent(location, source_line),
+                painter.paint(RED, &marker)
+            ));
+        }
+    }
+
+    if let Some(help) = fault.help() {
+        lines.push(format!(
+            "{}{}: {}",
+            painter.paint(BLUE, &format!("{}= ", " ".repeat(gutter_width))),
+            painter.paint(BOLD, "help"),
+            help
+        ));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+// ^の前の空白 タブはそのまま残して位置がずれないようにする
+fn indent(location: &Location, source_line: &str) -> String {
+    source_line
+        .chars()
+        .take(location.span.start.column - 1)
+        .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+        .collect()
+}
+
+// 複数行にまたがる場合は行末まで
+fn underline(location: &Location, source_line: &str) -> String {
+    let span = location.span;
+    let width = if span.start.line == span.end.line {
+        span.end.column.saturating_sub(span.start.column)
+    } else {
+        (source_line.chars().count() + 1).saturating_sub(span.start.column)
+    };
+    "^".repeat(width.max(1))
+}
+
+fn render_json(fault: &dyn Fault) -> String {
+    let mut json = json!({ "message": fault.msg() });
+    match fault.location() {
+        Some(location) => {
+            let span = location.span;
+            json["file"] = json!(location.file);
+            json["line"] = json!(span.start.line);
+            json["column"] = json!(span.start.column);
+            json["end_line"] = json!(span.end.line);
+            json["end_column"] = json!(span.end.column);
+        }
+        None => json["file"] = serde_json::Value::Null,
+    }
+    json["label"] = json!(fault.label());
+    json["help"] = json!(fault.help());
+
+    json.to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        fault::LocatedFault,
+        lexer::Token,
+        location::{Location, Position, Span},
+        parser::ParseFault,
+    };
+
+    use super::{render, ErrorFormat};
+
+    fn trailing_fault() -> LocatedFault {
+        LocatedFault::new(
+            Box::new(ParseFault::TrailingToken {
+                found: Token::Identifier("f".to_string()),
+            }),
+            Location {
+                file: "theme.dfr".to_string(),
+                span: Span::new(Position::new(2, 7), Position::new(2, 8)),
+            },
+        )
+    }
+
+    #[test]
+    fn render_human() {
+        let source = "a = #ffffff;\ne = a f;\n";
+        let rendered = render(&trailing_fault(), ErrorFormat::Human, false, Some(source));
+        assert_eq!(
+            rendered,
+            "\
+ParseError: unexpected identifier `f` after the end of the statement
+ --> theme.dfr:2:7
+  |
+2 | e = a f;
+  |       ^ unexpected token
+  = help: statements are separated by `;`
+"
+        );
+
+        let rendered = render(&trailing_fault(), ErrorFormat::Human, false, None);
+        assert_eq!(
+            rendered,
+            "\
+ParseError: unexpected identifier `f` after the end of the statement
+ --> theme.dfr:2:7
+  = help: statements are separated by `;`
+"
+        );
+
+        let rendered = render(&trailing_fault(), ErrorFormat::Human, true, Some(source));
+        assert!(rendered.starts_with("\x1b[1;31mParseError"));
+    }
+
+    #[test]
+    fn render_short_and_json() {
+        assert_eq!(
+            render(&trailing_fault(), ErrorFormat::Short, false, None),
+            "theme.dfr:2:7: ParseError: unexpected identifier `f` after the end of the statement"
+        );
+        assert_eq!(
+            render(&trailing_fault(), ErrorFormat::Json, false, None),
+            "{\"message\":\"ParseError: unexpected identifier `f` after the end of the statement\",\"file\":\"theme.dfr\",\"line\":2,\"column\":7,\"end_line\":2,\"end_column\":8,\"label\":\"unexpected token\",\"help\":\"statements are separated by `;`\"}"
+        );
+    }
+}
@@ -0,0 +1,228 @@
+// 変数をJSONやCSSなどの形式で出力する
+
+mod css;
+mod editor;
+mod json;
+mod less;
+mod scss;
+mod terminal;
+
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+
+use crate::{fault::Fault, value::Value};
+
+pub use self::css::CssEmitter;
+pub use self::editor::{ColorschemeEmitter, NeovimEmitter, VscodeEmitter};
+pub use self::json::{JsonColor, JsonEmitter};
+pub use self::less::LessEmitter;
+pub use self::scss::ScssEmitter;
+pub use self::terminal::{
+    AlacrittyEmitter, KittyEmitter, WeztermEmitter, WindowsTerminalEmitter, XresourcesEmitter,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    Json,
+    // `:root { --name: #hex; }`
+    Css,
+    // `$name: #hex;`
+    Scss,
+    // `@name: #hex;`
+    Less,
+    // ここから下はターミナルのテーマ `black`や`bright_red`などの変数を使う
+    Alacritty,
+    Kitty,
+    Wezterm,
+    Xresources,
+    WindowsTerminal,
+    // ここから下はエディタの配色 `highlight Normal.fg = fg;`で定義したハイライトグループを使う
+    Neovim,
+    Vscode,
+}
+
+// 出力する変数 名前の自然順に並んで渡される
+#[derive(Debug, Clone, Copy)]
+pub struct Variable<'a> {
+    pub name: &'a str,
+    pub value: &'a Value,
+    // `///`コメント
+    pub doc: Option<&'a str>,
+}
+
+pub trait Emitter {
+    fn emit(&self, vars: &[Variable]) -> Result<String, EmitFault>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EmitFault {
+    // 使えない文字を置き換えた結果、別の変数と同じ名前になった
+    NameCollision {
+        format: &'static str,
+        first: String,
+        second: String,
+        name: String,
+    },
+    // テーマに必要な変数が定義されていない
+    MissingSlots {
+        format: &'static str,
+        missing: Vec<&'static str>,
+    },
+    SlotType {
+        format: &'static str,
+        slot: &'static str,
+        got: String,
+    },
+    // エディタの配色にハイライトグループが1つもない
+    NoHighlights {
+        format: &'static str,
+    },
+}
+
+impl Fault for EmitFault {
+    fn msg(&self) -> String {
+        match self {
+            EmitFault::NameCollision {
+                format,
+                first,
+                second,
+                name,
+            } => format!(
+                "EmitError: `{}` and `{}` are both written as `{}` in {}",
+                first, second, name, format
+            ),
+            EmitFault::MissingSlots { format, missing } => {
+                let names: Vec<String> = missing.iter().map(|name| format!("`{}`", name)).collect();
+                format!(
+                    "EmitError: {} theme requires colors that are not defined: {}",
+                    format,
+                    names.join(", ")
+                )
+            }
+            EmitFault::SlotType { format, slot, got } => format!(
+                "EmitError: `{}` must be a color for {} theme, found {}",
+                slot, format, got
+            ),
+            EmitFault::NoHighlights { format } => format!(
+                "EmitError: {} colorscheme requires highlight groups but none are defined",
+                format
+            ),
+        }
+    }
+
+    fn help(&self) -> Option<String> {
+        match self {
+            EmitFault::NameCollision { .. } => {
+                Some("rename one of the variables so that they differ".to_string())
+            }
+            EmitFault::MissingSlots { missing, .. } => Some(format!(
+                "define them as color variables, e.g. `{} = #000000;`",
+                missing[0]
+            )),
+            EmitFault::NoHighlights { .. } => {
+                Some("define highlight groups, e.g. `highlight Normal.fg = #c0c0c0;`".to_string())
+            }
+            EmitFault::SlotType { .. } => None,
+        }
+    }
+}
+
+// 出力先で使えない文字を`-`にした名前を作り、重なりがないか確かめる
+// keyは重なりを判定するための名前 SCSSは`_`と`-`を区別しないため
+pub(crate) fn sanitize_names(
+    format: &'static str,
+    vars: &[Variable],
+    sanitize: impl Fn(&str) -> String,
+    key: impl Fn(&str) -> String,
+) -> Result<Vec<String>, EmitFault> {
+    let mut seen: HashMap<String, &str> = HashMap::new();
+    let mut names = Vec::with_capacity(vars.len());
+    for var in vars {
+        let name = sanitize(var.name);
+        if let Some(first) = seen.insert(key(&name), var.name) {
+            return Err(EmitFault::NameCollision {
+                format,
+                first: first.to_string(),
+                second: var.name.to_string(),
+                name,
+            });
+        }
+        names.push(name);
+    }
+    Ok(names)
+}
+
+// CSSの識別子に使える文字 英数字と`-` `_`とASCII以外
+pub(crate) fn is_css_name_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' || !ch.is_ascii()
+}
+
+// CSS、SCSS、Lessで共通の値の書き方
+pub(crate) fn css_value(value: &Value) -> String {
+    match value {
+        Value::Color(color) => color.to_hex_string(),
+        Value::Number(number) => number.to_string(),
+        Value::Percent(number) => format!("{}%", number),
+        Value::Str(text) => {
+            let mut buf = String::from("\"");
+            for ch in text.chars() {
+                match ch {
+                    '"' => buf += "\\\"",
+                    '\\' => buf += "\\\\",
+                    '\n' => buf += "\\a ",
+                    ch => buf.push(ch),
+                }
+            }
+            buf.push('"');
+            buf
+        }
+        Value::Palette(palette) => {
+            let colors: Vec<String> = palette.iter().map(|(_, c)| c.to_hex_string()).collect();
+            colors.join(" ")
+        }
+    }
+}
+
+// `// doc`の形式 複数行のコメントは行ごとに`//`をつける
+pub(crate) fn line_comments(doc: &str, indent: &str) -> String {
+    doc.lines()
+        .map(|line| format!("{}// {}\n", indent, line))
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use crate::{color::Color, value::Value};
+
+    use super::{css_value, Variable};
+
+    // 各出力形式のテストで使う docsは(名前, コメント)
+    pub(crate) fn as_variables<'a>(
+        values: &'a [(&'static str, Value)],
+        docs: &[(&str, &'a str)],
+    ) -> Vec<Variable<'a>> {
+        values
+            .iter()
+            .map(|(name, value)| Variable {
+                name,
+                value,
+                doc: docs
+                    .iter()
+                    .find(|(doc_name, _)| doc_name == name)
+                    .map(|(_, doc)| *doc),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn _css_value() {
+        assert_eq!(css_value(&Value::Color(Color::new(1, 2, 3))), "#010203");
+        assert_eq!(css_value(&Value::Number(0.5)), "0.5");
+        assert_eq!(css_value(&Value::Percent(50.0)), "50%");
+        assert_eq!(
+            css_value(&Value::Str("a \"b\"\n".to_string())),
+            "\"a \\\"b\\\"\\a \""
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use super::{css_value, is_css_name_char, sanitize_names, EmitFault, Emitter, Variable};
+
+// selectorのブロックにカスタムプロパティとして書く
+// prefixは`--`のすぐ後ろにつく `theme-`なら`--theme-accent`
+pub struct CssEmitter {
+    pub selector: String,
+    pub prefix: String,
+}
+
+impl Default for CssEmitter {
+    fn default() -> Self {
+        CssEmitter {
+            selector: ":root".to_string(),
+            prefix: String::new(),
+        }
+    }
+}
+
+// `blue.500`は`blue-500`になる
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|ch| if is_css_name_char(ch) { ch } else { '-' })
+        .collect()
+}
+
+impl Emitter for CssEmitter {
+    fn emit(&self, vars: &[Variable]) -> Result<String, EmitFault> {
+        let prefix = sanitize(&self.prefix);
+        let names = sanitize_names(
+            "CSS",
+            vars,
+            |name| format!("--{}{}", prefix, sanitize(name)),
+            |name| name.to_string(),
+        )?;
+
+        let mut buf = format!("{} {{\n", self.selector);
+        for (var, name) in vars.iter().zip(names) {
+            // `*/`があるとコメントが途中で終わってしまう
+            for line in var.doc.iter().flat_map(|doc| doc.lines()) {
+                buf += &format!("  /* {} */\n", line.replace("*/", "* /"));
+            }
+            buf += &format!("  {}: {};\n", name, css_value(var.value));
+        }
+        buf += "}";
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        color::Color,
+        emit::{test::as_variables, EmitFault, Emitter},
+        value::Value,
+    };
+
+    use super::CssEmitter;
+
+    #[test]
+    fn emit() {
+        let values = [
+            ("bg", Value::Color(Color::new(0, 0, 0))),
+            ("blue.500", Value::Color(Color::new(0, 0, 255))),
+            ("gap/ratio", Value::Number(1.5)),
+        ];
+        let vars = as_variables(&values, &[("bg", "Background */ color")]);
+
+        assert_eq!(
+            CssEmitter::default().emit(&vars),
+            Ok(":root {\n  /* Background * / color */\n  --bg: #000000;\n  --blue-500: #0000ff;\n  --gap-ratio: 1.5;\n}".to_string())
+        );
+
+        let emitter = CssEmitter {
+            selector: "[data-theme=\"dark\"]".to_string(),
+            prefix: "theme.".to_string(),
+        };
+        assert_eq!(
+            emitter.emit(&vars[..1]),
+            Ok(
+                "[data-theme=\"dark\"] {\n  /* Background * / color */\n  --theme-bg: #000000;\n}"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn name_collision() {
+        let values = [
+            ("blue-500", Value::Number(1.0)),
+            ("blue.500", Value::Number(2.0)),
+        ];
+        assert_eq!(
+            CssEmitter::default().emit(&as_variables(&values, &[])),
+            Err(EmitFault::NameCollision {
+                format: "CSS",
+                first: "blue-500".to_string(),
+                second: "blue.500".to_string(),
+                name: "--blue-500".to_string(),
+            })
+        );
+    }
+}
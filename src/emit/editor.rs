@@ -0,0 +1,68 @@
+// エディタの配色 `highlight Normal.fg = fg;`のようなハイライトグループの定義から作る
+
+mod neovim;
+mod vscode;
+
+use crate::{color::Color, highlight::Highlight};
+
+use super::EmitFault;
+
+pub use self::neovim::NeovimEmitter;
+pub use self::vscode::VscodeEmitter;
+
+// 変数ではなくハイライトグループを出力する
+pub trait ColorschemeEmitter {
+    fn emit(&self, highlights: &[Highlight]) -> Result<String, EmitFault>;
+}
+
+// グループが1つもなければエラー
+fn check_highlights(format: &'static str, highlights: &[Highlight]) -> Result<(), EmitFault> {
+    if highlights.is_empty() {
+        return Err(EmitFault::NoHighlights { format });
+    }
+    Ok(())
+}
+
+// アルファ値はどちらの形式でも使えないので無視する
+fn hex(color: &Color) -> String {
+    color.with_alpha(u8::MAX).to_hex_string()
+}
+
+// Normalの背景が明るければlight Normal.bgがなければdark
+fn is_light(highlights: &[Highlight]) -> bool {
+    let black = Color::new(0, 0, 0);
+    let white = Color::new(255, 255, 255);
+    highlights
+        .iter()
+        .find(|hl| hl.group == "Normal")
+        .and_then(|hl| hl.bg.as_ref())
+        .is_some_and(|bg| bg.contrast(&black) > bg.contrast(&white))
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use crate::{color::Color, highlight::Highlight};
+
+    use super::is_light;
+
+    pub(crate) fn highlight(group: &str, fg: Option<Color>, style: &[&'static str]) -> Highlight {
+        Highlight {
+            group: group.to_string(),
+            fg,
+            style: style.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn light() {
+        let mut normal = highlight("Normal", Some(Color::new(0, 0, 0)), &[]);
+        assert!(!is_light(&[normal.clone()]));
+
+        normal.bg = Some(Color::new(0xee, 0xee, 0xee));
+        assert!(is_light(&[normal.clone()]));
+
+        normal.bg = Some(Color::new(0x20, 0x20, 0x20));
+        assert!(!is_light(&[normal]));
+    }
+}
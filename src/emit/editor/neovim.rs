@@ -0,0 +1,106 @@
+use crate::{highlight::Highlight, utils::json_string};
+
+use super::{check_highlights, hex, is_light, ColorschemeEmitter, EmitFault};
+
+// `colors/<name>.lua`として置くLuaのcolorscheme
+pub struct NeovimEmitter {
+    // `:colorscheme`で指定する名前
+    pub name: String,
+}
+
+impl ColorschemeEmitter for NeovimEmitter {
+    fn emit(&self, highlights: &[Highlight]) -> Result<String, EmitFault> {
+        check_highlights("Neovim", highlights)?;
+
+        let background = if is_light(highlights) {
+            "light"
+        } else {
+            "dark"
+        };
+        let mut lines = vec![
+            "vim.cmd(\"highlight clear\")".to_string(),
+            "if vim.fn.exists(\"syntax_on\") == 1 then".to_string(),
+            "  vim.cmd(\"syntax reset\")".to_string(),
+            "end".to_string(),
+            format!("vim.o.background = \"{}\"", background),
+            format!("vim.g.colors_name = {}", json_string(&self.name)),
+            String::new(),
+        ];
+
+        for hl in highlights {
+            let mut attrs = Vec::new();
+            for (key, color) in [("fg", &hl.fg), ("bg", &hl.bg), ("sp", &hl.sp)] {
+                if let Some(color) = color {
+                    attrs.push(format!("{} = \"{}\"", key, hex(color)));
+                }
+            }
+            for style in &hl.style {
+                attrs.push(format!("{} = true", style));
+            }
+
+            let attrs = match attrs.is_empty() {
+                true => "{}".to_string(),
+                false => format!("{{ {} }}", attrs.join(", ")),
+            };
+            lines.push(format!(
+                "vim.api.nvim_set_hl(0, {}, {})",
+                json_string(&hl.group),
+                attrs
+            ));
+        }
+
+        Ok(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        color::Color,
+        emit::{editor::test::highlight, EmitFault},
+    };
+
+    use super::{ColorschemeEmitter, NeovimEmitter};
+
+    #[test]
+    fn emit() {
+        let mut normal = highlight("Normal", Some(Color::new(0xc0, 0xc0, 0xc0)), &[]);
+        normal.bg = Some(Color::new_rgba(0, 0, 0, 0x80));
+        let mut spell = highlight("SpellBad", None, &["undercurl"]);
+        spell.sp = Some(Color::new(255, 0, 0));
+        let highlights = [
+            normal,
+            highlight(
+                "@comment",
+                Some(Color::new(0x80, 0x80, 0x80)),
+                &["bold", "italic"],
+            ),
+            spell,
+            highlight("Ignore", None, &[]),
+        ];
+
+        let emitter = NeovimEmitter {
+            name: "night".to_string(),
+        };
+        assert_eq!(
+            emitter.emit(&highlights).unwrap(),
+            "\
+vim.cmd(\"highlight clear\")
+if vim.fn.exists(\"syntax_on\") == 1 then
+  vim.cmd(\"syntax reset\")
+end
+vim.o.background = \"dark\"
+vim.g.colors_name = \"night\"
+
+vim.api.nvim_set_hl(0, \"Normal\", { fg = \"#c0c0c0\", bg = \"#000000\" })
+vim.api.nvim_set_hl(0, \"@comment\", { fg = \"#808080\", bold = true, italic = true })
+vim.api.nvim_set_hl(0, \"SpellBad\", { sp = \"#ff0000\", undercurl = true })
+vim.api.nvim_set_hl(0, \"Ignore\", {})"
+        );
+
+        assert_eq!(
+            emitter.emit(&[]),
+            Err(EmitFault::NoHighlights { format: "Neovim" })
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use serde_json::{json, Map};
+
+use crate::{
+    color::Color,
+    highlight::{Highlight, HighlightAttr},
+};
+
+use super::{check_highlights, hex, is_light, ColorschemeEmitter, EmitFault};
+
+// `*-color-theme.json` 対応するキーやスコープのないグループは無視する
+pub struct VscodeEmitter {
+    pub name: String,
+}
+
+// (グループ, 属性, "colors"のキー)
+const UI_KEYS: [(&str, HighlightAttr, &str); 25] = [
+    ("Normal", HighlightAttr::Fg, "editor.foreground"),
+    ("Normal", HighlightAttr::Bg, "editor.background"),
+    (
+        "CursorLine",
+        HighlightAttr::Bg,
+        "editor.lineHighlightBackground",
+    ),
+    ("Visual", HighlightAttr::Bg, "editor.selectionBackground"),
+    ("LineNr", HighlightAttr::Fg, "editorLineNumber.foreground"),
+    (
+        "CursorLineNr",
+        HighlightAttr::Fg,
+        "editorLineNumber.activeForeground",
+    ),
+    ("Cursor", HighlightAttr::Bg, "editorCursor.foreground"),
+    ("Pmenu", HighlightAttr::Fg, "editorSuggestWidget.foreground"),
+    ("Pmenu", HighlightAttr::Bg, "editorSuggestWidget.background"),
+    (
+        "PmenuSel",
+        HighlightAttr::Fg,
+        "editorSuggestWidget.selectedForeground",
+    ),
+    (
+        "PmenuSel",
+        HighlightAttr::Bg,
+        "editorSuggestWidget.selectedBackground",
+    ),
+    ("StatusLine", HighlightAttr::Fg, "statusBar.foreground"),
+    ("StatusLine", HighlightAttr::Bg, "statusBar.background"),
+    (
+        "Search",
+        HighlightAttr::Bg,
+        "editor.findMatchHighlightBackground",
+    ),
+    ("IncSearch", HighlightAttr::Bg, "editor.findMatchBackground"),
+    ("NormalFloat", HighlightAttr::Fg, "editorWidget.foreground"),
+    ("NormalFloat", HighlightAttr::Bg, "editorWidget.background"),
+    ("VertSplit", HighlightAttr::Fg, "editorGroup.border"),
+    ("WinSeparator", HighlightAttr::Fg, "editorGroup.border"),
+    (
+        "DiagnosticError",
+        HighlightAttr::Fg,
+        "editorError.foreground",
+    ),
+    (
+        "DiagnosticWarn",
+        HighlightAttr::Fg,
+        "editorWarning.foreground",
+    ),
+    ("DiagnosticInfo", HighlightAttr::Fg, "editorInfo.foreground"),
+    ("DiagnosticHint", HighlightAttr::Fg, "editorHint.foreground"),
+    (
+        "DiffAdd",
+        HighlightAttr::Bg,
+        "diffEditor.insertedTextBackground",
+    ),
+    (
+        "DiffDelete",
+        HighlightAttr::Bg,
+        "diffEditor.removedTextBackground",
+    ),
+];
+
+// (グループ, TextMateのスコープ) tree-sitterのグループも同じスコープにする
+const TOKEN_SCOPES: [(&str, &[&str]); 30] = [
+    ("Comment", &["comment"]),
+    ("@comment", &["comment"]),
+    ("String", &["string"]),
+    ("@string", &["string"]),
+    ("Character", &["constant.character"]),
+    ("@character", &["constant.character"]),
+    ("Number", &["constant.numeric"]),
+    ("@number", &["constant.numeric"]),
+    ("Boolean", &["constant.language"]),
+    ("@boolean", &["constant.language"]),
+    ("Constant", &["constant"]),
+    ("@constant", &["constant"]),
+    ("Identifier", &["variable"]),
+    ("@variable", &["variable"]),
+    ("Function", &["entity.name.function", "support.function"]),
+    ("@function", &["entity.name.function", "support.function"]),
+    ("Statement", &["keyword"]),
+    ("Keyword", &["keyword"]),
+    ("@keyword", &["keyword"]),
+    ("Operator", &["keyword.operator"]),
+    ("@operator", &["keyword.operator"]),
+    (
+        "Type",
+        &["entity.name.type", "storage.type", "support.type"],
+    ),
+    (
+        "@type",
+        &["entity.name.type", "storage.type", "support.type"],
+    ),
+    ("PreProc", &["meta.preprocessor"]),
+    ("Special", &["constant.character.escape"]),
+    ("@string.escape", &["constant.character.escape"]),
+    ("Tag", &["entity.name.tag"]),
+    ("@tag", &["entity.name.tag"]),
+    ("Error", &["invalid"]),
+    ("@error", &["invalid"]),
+];
+
+// VS Codeにundercurlとreverseはない undercurlは下線にする
+fn font_style(style: &[&str]) -> Vec<&'static str> {
+    let mut font_style = Vec::new();
+    for style in style {
+        let style = match *style {
+            "bold" => "bold",
+            "italic" => "italic",
+            "underline" | "undercurl" => "underline",
+            "strikethrough" => "strikethrough",
+            _ => continue,
+        };
+        if !font_style.contains(&style) {
+            font_style.push(style);
+        }
+    }
+    font_style
+}
+
+fn attr_color(hl: &Highlight, attr: HighlightAttr) -> Option<&Color> {
+    match attr {
+        HighlightAttr::Fg => hl.fg.as_ref(),
+        HighlightAttr::Bg => hl.bg.as_ref(),
+        HighlightAttr::Sp => hl.sp.as_ref(),
+        HighlightAttr::Style => None,
+    }
+}
+
+impl ColorschemeEmitter for VscodeEmitter {
+    fn emit(&self, highlights: &[Highlight]) -> Result<String, EmitFault> {
+        check_highlights("VS Code", highlights)?;
+
+        let mut colors = Map::new();
+        for (group, attr, key) in UI_KEYS {
+            let hl = highlights.iter().find(|hl| hl.group == group);
+            if let Some(color) = hl.and_then(|hl| attr_color(hl, attr)) {
+                colors.insert(key.to_string(), json!(hex(color)));
+            }
+        }
+
+        // 定義された順に並べる 後のルールが優先されるため
+        let mut tokens = Vec::new();
+        for hl in highlights {
+            let Some((_, scopes)) = TOKEN_SCOPES.iter().find(|(group, _)| *group == hl.group)
+            else {
+                continue;
+            };
+
+            let mut settings = Map::new();
+            if let Some(fg) = &hl.fg {
+                settings.insert("foreground".to_string(), json!(hex(fg)));
+            }
+            let font_style = font_style(&hl.style);
+            if !font_style.is_empty() {
+                settings.insert("fontStyle".to_string(), json!(font_style.join(" ")));
+            }
+            if settings.is_empty() {
+                continue;
+            }
+
+            tokens.push(json!({
+                "name": hl.group,
+                "scope": scopes,
+                "settings": settings,
+            }));
+        }
+
+        let theme_type = if is_light(highlights) {
+            "light"
+        } else {
+            "dark"
+        };
+        let theme = json!({
+            "name": self.name,
+            "type": theme_type,
+            "colors": colors,
+            "tokenColors": tokens,
+        });
+        Ok(serde_json::to_string_pretty(&theme).expect("bug"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{color::Color, emit::editor::test::highlight};
+
+    use super::{font_style, ColorschemeEmitter, VscodeEmitter};
+
+    #[test]
+    fn emit() {
+        let mut normal = highlight("Normal", Some(Color::new(0x20, 0x20, 0x20)), &[]);
+        normal.bg = Some(Color::new(0xff, 0xff, 0xff));
+        let highlights = [
+            normal,
+            highlight("Comment", Some(Color::new(0x80, 0x80, 0x80)), &["italic"]),
+            highlight("Keyword", None, &["bold"]),
+            highlight("Conceal", Some(Color::new(0, 0, 0)), &[]),
+        ];
+
+        let emitter = VscodeEmitter {
+            name: "Day".to_string(),
+        };
+        assert_eq!(
+            emitter.emit(&highlights).unwrap(),
+            r##"{
+  "name": "Day",
+  "type": "light",
+  "colors": {
+    "editor.foreground": "#202020",
+    "editor.background": "#ffffff"
+  },
+  "tokenColors": [
+    {
+      "name": "Comment",
+      "scope": [
+        "comment"
+      ],
+      "settings": {
+        "foreground": "#808080",
+        "fontStyle": "italic"
+      }
+    },
+    {
+      "name": "Keyword",
+      "scope": [
+        "keyword"
+      ],
+      "settings": {
+        "fontStyle": "bold"
+      }
+    }
+  ]
+}"##
+        );
+    }
+
+    #[test]
+    fn _font_style() {
+        assert_eq!(
+            font_style(&["undercurl", "reverse", "underline", "bold"]),
+            vec!["underline", "bold"]
+        );
+    }
+}
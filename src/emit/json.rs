@@ -0,0 +1,278 @@
+use std::collections::HashSet;
+
+use clap::ValueEnum;
+use serde_json::{json, Map};
+
+use crate::{color::Color, utils::round_for_display, value::Value};
+
+use super::{EmitFault, Emitter, Variable};
+
+// `{"name": value}` with_docsの場合は`{"name": {"value": value, "description": doc}}`
+#[derive(Default)]
+pub struct JsonEmitter {
+    pub with_docs: bool,
+    // 2文字のインデントで改行する
+    pub pretty: bool,
+    // `ui.bg.hover`を`{"ui": {"bg": {"hover": value}}}`にする
+    pub nested: bool,
+    pub color: JsonColor,
+}
+
+// 色の書き方
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum)]
+pub enum JsonColor {
+    // `"#rrggbb"` 不透明でなければ`"#rrggbbaa"`
+    #[default]
+    Hex,
+    // `{"r": 29, "g": 31, "b": 33}` 不透明でなければ0から1の`"a"`をつける
+    RgbObject,
+    // `[29, 31, 33]` 不透明でなければ0から1のアルファ値をつける
+    Array,
+}
+
+// `blue`と`blue.50`のように値と子の両方がある名前は、値を`DEFAULT`に置く
+const DEFAULT_KEY: &str = "DEFAULT";
+
+impl Emitter for JsonEmitter {
+    fn emit(&self, vars: &[Variable]) -> Result<String, EmitFault> {
+        let names: HashSet<&str> = vars.iter().map(|var| var.name).collect();
+        let mut root = Map::new();
+        for var in vars {
+            let mut value = json_value(var.value, self.color);
+            if self.with_docs {
+                value = json!({ "value": value, "description": var.doc });
+            }
+
+            if self.nested {
+                insert_nested(&mut root, &names, var.name, value)?;
+            } else {
+                root.insert(var.name.to_string(), value);
+            }
+        }
+
+        let root = serde_json::Value::Object(root);
+        let json = match self.pretty {
+            true => serde_json::to_string_pretty(&root),
+            false => serde_json::to_string(&root),
+        };
+        Ok(json.expect("bug"))
+    }
+}
+
+// 名前の自然順に並んでいるので、親は子より先に来る
+// namesはすべての変数の名前 途中のキーが値を持つ変数かどうかを調べる
+fn insert_nested(
+    root: &mut Map<String, serde_json::Value>,
+    names: &HashSet<&str>,
+    name: &str,
+    value: serde_json::Value,
+) -> Result<(), EmitFault> {
+    let keys: Vec<&str> = name.split('.').collect();
+    let (last, parents) = keys.split_last().expect("bug");
+
+    let mut object = root;
+    for i in 0..parents.len() {
+        let entry = object
+            .entry(parents[i])
+            .or_insert_with(|| serde_json::Value::Object(Map::new()));
+        if names.contains(parents[..=i].join(".").as_str()) && entry.get(DEFAULT_KEY).is_none() {
+            let mut children = Map::new();
+            children.insert(DEFAULT_KEY.to_string(), entry.take());
+            *entry = serde_json::Value::Object(children);
+        }
+        // `a`と`a.DEFAULT.b`のように、`DEFAULT`に置いた値の下に子を置こうとした
+        let Some(children) = entry.as_object_mut() else {
+            let owner = match parents[i] {
+                DEFAULT_KEY => &parents[..i],
+                _ => &parents[..=i],
+            };
+            return Err(EmitFault::NameCollision {
+                format: "nested JSON",
+                first: owner.join("."),
+                second: name.to_string(),
+                name: parents[..=i].join("."),
+            });
+        };
+        object = children;
+    }
+
+    // `blue`と`blue.DEFAULT`の両方がある
+    if object.contains_key(*last) {
+        return Err(EmitFault::NameCollision {
+            format: "nested JSON",
+            first: parents.join("."),
+            second: name.to_string(),
+            name: name.to_string(),
+        });
+    }
+    object.insert(last.to_string(), value);
+    Ok(())
+}
+
+fn json_value(value: &Value, color: JsonColor) -> serde_json::Value {
+    match value {
+        Value::Color(c) => json_color(c, color),
+        // 整数は`10.0`ではなく`10`にする
+        Value::Number(number) if number.fract() == 0.0 && number.abs() < 1e15 => {
+            json!(*number as i64)
+        }
+        Value::Number(number) => json!(number),
+        Value::Percent(number) => json!(format!("{}%", number)),
+        Value::Str(text) => json!(text),
+        Value::Palette(palette) => palette.iter().map(|(_, c)| json_color(c, color)).collect(),
+    }
+}
+
+fn json_color(c: &Color, color: JsonColor) -> serde_json::Value {
+    let alpha = round_for_display(c.alpha() as f64 / 255.0);
+    match color {
+        JsonColor::Hex => json!(c.to_hex_string()),
+        JsonColor::RgbObject if c.is_opaque() => {
+            json!({ "r": c.red(), "g": c.green(), "b": c.blue() })
+        }
+        JsonColor::RgbObject => json!({ "r": c.red(), "g": c.green(), "b": c.blue(), "a": alpha }),
+        JsonColor::Array if c.is_opaque() => json!([c.red(), c.green(), c.blue()]),
+        JsonColor::Array => json!([c.red(), c.green(), c.blue(), alpha]),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        color::Color,
+        emit::{test::as_variables, EmitFault, Emitter},
+        value::Value,
+    };
+
+    use super::{JsonColor, JsonEmitter};
+
+    fn emit(emitter: JsonEmitter, values: &[(&'static str, Value)]) -> Result<String, EmitFault> {
+        emitter.emit(&as_variables(values, &[("ui.bg", "背景")]))
+    }
+
+    #[test]
+    fn escape() {
+        let values = [("a\"b\\c", Value::Str("\"\n\u{1b}".to_string()))];
+        assert_eq!(
+            emit(JsonEmitter::default(), &values),
+            Ok("{\"a\\\"b\\\\c\":\"\\\"\\n\\u001b\"}".to_string())
+        );
+    }
+
+    #[test]
+    fn color_formats() {
+        let values = [
+            ("a", Value::Color(Color::new(1, 2, 3))),
+            ("b", Value::Color(Color::new_rgba(1, 2, 3, 128))),
+        ];
+        let json = |color| {
+            emit(
+                JsonEmitter {
+                    color,
+                    ..Default::default()
+                },
+                &values,
+            )
+        };
+        assert_eq!(
+            json(JsonColor::RgbObject),
+            Ok(r#"{"a":{"r":1,"g":2,"b":3},"b":{"r":1,"g":2,"b":3,"a":0.5}}"#.to_string())
+        );
+        assert_eq!(
+            json(JsonColor::Array),
+            Ok(r#"{"a":[1,2,3],"b":[1,2,3,0.5]}"#.to_string())
+        );
+    }
+
+    #[test]
+    fn nested() {
+        let values = [
+            ("blue", Value::Color(Color::new(0, 0, 255))),
+            ("blue.50", Value::Color(Color::new(0, 0, 50))),
+            ("gap", Value::Number(4.0)),
+            ("ui.bg", Value::Color(Color::new(0, 0, 0))),
+            ("ui.bg.hover", Value::Color(Color::new(1, 1, 1))),
+        ];
+        let nested = JsonEmitter {
+            nested: true,
+            pretty: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            emit(nested, &values).unwrap(),
+            r##"{
+  "blue": {
+    "DEFAULT": "#0000ff",
+    "50": "#000032"
+  },
+  "gap": 4,
+  "ui": {
+    "bg": {
+      "DEFAULT": "#000000",
+      "hover": "#010101"
+    }
+  }
+}"##
+        );
+
+        let rgb_object = JsonEmitter {
+            nested: true,
+            color: JsonColor::RgbObject,
+            ..Default::default()
+        };
+        assert_eq!(
+            emit(rgb_object, &values[3..]).unwrap(),
+            r#"{"ui":{"bg":{"DEFAULT":{"r":0,"g":0,"b":0},"hover":{"r":1,"g":1,"b":1}}}}"#
+        );
+
+        let with_docs = JsonEmitter {
+            nested: true,
+            with_docs: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            emit(with_docs, &values[3..]).unwrap(),
+            r##"{"ui":{"bg":{"DEFAULT":{"value":"#000000","description":"背景"},"hover":{"value":"#010101","description":null}}}}"##
+        );
+    }
+
+    #[test]
+    fn nested_collision() {
+        let values = [
+            ("blue", Value::Color(Color::new(0, 0, 255))),
+            ("blue.50", Value::Color(Color::new(0, 0, 50))),
+            ("blue.DEFAULT", Value::Color(Color::new(0, 0, 1))),
+        ];
+        let nested = JsonEmitter {
+            nested: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            emit(nested, &values),
+            Err(EmitFault::NameCollision {
+                format: "nested JSON",
+                first: "blue".to_string(),
+                second: "blue.DEFAULT".to_string(),
+                name: "blue.DEFAULT".to_string(),
+            })
+        );
+
+        let values = [
+            ("a", Value::Color(Color::new(0, 0, 0))),
+            ("a.DEFAULT.b", Value::Color(Color::new(1, 1, 1))),
+        ];
+        let nested = JsonEmitter {
+            nested: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            emit(nested, &values),
+            Err(EmitFault::NameCollision {
+                format: "nested JSON",
+                first: "a".to_string(),
+                second: "a.DEFAULT.b".to_string(),
+                name: "a.DEFAULT".to_string(),
+            })
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use super::{
+    css_value, is_css_name_char, line_comments, sanitize_names, EmitFault, Emitter, Variable,
+};
+
+// `@name: value;`
+pub struct LessEmitter;
+
+// Lessの変数名は英数字と`-` `_`だけ 数字から始まってもよい
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|ch| {
+            if is_css_name_char(ch) && ch.is_ascii() {
+                ch
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+impl Emitter for LessEmitter {
+    fn emit(&self, vars: &[Variable]) -> Result<String, EmitFault> {
+        let names = sanitize_names(
+            "Less",
+            vars,
+            |name| format!("@{}", sanitize(name)),
+            |name| name.to_string(),
+        )?;
+
+        let mut buf = String::new();
+        for (var, name) in vars.iter().zip(names) {
+            buf += &line_comments(var.doc.unwrap_or_default(), "");
+            buf += &format!("{}: {};\n", name, css_value(var.value));
+        }
+        Ok(buf.trim_end().to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        color::Color,
+        emit::{test::as_variables, Emitter},
+        value::Value,
+    };
+
+    use super::LessEmitter;
+
+    #[test]
+    fn emit() {
+        let values = [
+            ("bg", Value::Color(Color::new_rgba(0, 0, 0, 128))),
+            ("blue.500", Value::Color(Color::new(0, 0, 255))),
+            ("100", Value::Number(2.0)),
+            ("grün", Value::Number(1.0)),
+        ];
+        let vars = as_variables(&values, &[("bg", "Background")]);
+
+        assert_eq!(
+            LessEmitter.emit(&vars),
+            Ok(
+                "// Background\n@bg: #00000080;\n@blue-500: #0000ff;\n@100: 2;\n@gr-n: 1;"
+                    .to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use super::{
+    css_value, is_css_name_char, line_comments, sanitize_names, EmitFault, Emitter, Variable,
+};
+
+// `$name: value;`
+pub struct ScssEmitter;
+
+// 使えない文字は`-`にする
+// 数字や`-数字`から始まる名前は識別子にならないので先頭に`_`をつける
+fn sanitize(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|ch| if is_css_name_char(ch) { ch } else { '-' })
+        .collect();
+
+    let mut chars = name.chars();
+    let starts_with_digit = match chars.next() {
+        Some('-') => chars.next().is_none_or(|ch| ch.is_ascii_digit()),
+        Some(ch) => ch.is_ascii_digit(),
+        None => true,
+    };
+    if starts_with_digit {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+impl Emitter for ScssEmitter {
+    fn emit(&self, vars: &[Variable]) -> Result<String, EmitFault> {
+        // Sassは`$a_b`と`$a-b`を同じ変数として扱う
+        let names = sanitize_names(
+            "SCSS",
+            vars,
+            |name| format!("${}", sanitize(name)),
+            |name| name.replace('_', "-"),
+        )?;
+
+        let mut buf = String::new();
+        for (var, name) in vars.iter().zip(names) {
+            buf += &line_comments(var.doc.unwrap_or_default(), "");
+            buf += &format!("{}: {};\n", name, css_value(var.value));
+        }
+        Ok(buf.trim_end().to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        color::Color,
+        emit::{test::as_variables, EmitFault, Emitter},
+        value::Value,
+    };
+
+    use super::ScssEmitter;
+
+    #[test]
+    fn emit() {
+        let values = [
+            ("bg", Value::Color(Color::new(0, 0, 0))),
+            ("blue.500", Value::Color(Color::new(0, 0, 255))),
+            ("100", Value::Percent(50.0)),
+            ("label", Value::Str("dark".to_string())),
+        ];
+        let vars = as_variables(&values, &[("bg", "Background\nbehind text")]);
+
+        assert_eq!(
+            ScssEmitter.emit(&vars),
+            Ok("// Background\n// behind text\n$bg: #000000;\n$blue-500: #0000ff;\n$_100: 50%;\n$label: \"dark\";".to_string())
+        );
+    }
+
+    #[test]
+    fn name_collision() {
+        let values = [("a_b", Value::Number(1.0)), ("a.b", Value::Number(2.0))];
+        assert_eq!(
+            ScssEmitter.emit(&as_variables(&values, &[])),
+            Err(EmitFault::NameCollision {
+                format: "SCSS",
+                first: "a_b".to_string(),
+                second: "a.b".to_string(),
+                name: "$a-b".to_string(),
+            })
+        );
+    }
+}
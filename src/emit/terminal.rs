@@ -0,0 +1,160 @@
+// ターミナルエミュレータのテーマ
+// `black` `bright_red` `foreground`などの名前の変数を各形式のキーに対応させる
+
+mod alacritty;
+mod kitty;
+mod wezterm;
+mod windows_terminal;
+mod xresources;
+
+use std::collections::HashMap;
+
+use crate::{color::Color, value::Value};
+
+use super::{EmitFault, Variable};
+
+pub use self::alacritty::AlacrittyEmitter;
+pub use self::kitty::KittyEmitter;
+pub use self::wezterm::WeztermEmitter;
+pub use self::windows_terminal::WindowsTerminalEmitter;
+pub use self::xresources::XresourcesEmitter;
+
+// color0からcolor7
+const ANSI: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+// color8からcolor15
+const BRIGHT: [&str; 8] = [
+    "bright_black",
+    "bright_red",
+    "bright_green",
+    "bright_yellow",
+    "bright_blue",
+    "bright_magenta",
+    "bright_cyan",
+    "bright_white",
+];
+// 定義されていなければ出力しない
+const OPTIONAL: [&str; 4] = [
+    "cursor",
+    "cursor_text",
+    "selection_background",
+    "selection_foreground",
+];
+
+fn required_slots() -> impl Iterator<Item = &'static str> {
+    ["foreground", "background"]
+        .into_iter()
+        .chain(ANSI)
+        .chain(BRIGHT)
+}
+
+// テーマに使う色 アルファ値はどの形式でも使えないので無視する
+struct Slots {
+    colors: HashMap<&'static str, Color>,
+}
+
+impl Slots {
+    // 足りないslotはまとめて1つのエラーにする 色でない値のエラーより優先する
+    fn from_vars(format: &'static str, vars: &[Variable]) -> Result<Self, EmitFault> {
+        let mut colors = HashMap::new();
+        let mut missing = Vec::new();
+        let mut wrong_type = None;
+        for slot in required_slots().chain(OPTIONAL) {
+            match vars.iter().find(|var| var.name == slot) {
+                Some(Variable {
+                    value: Value::Color(color),
+                    ..
+                }) => {
+                    colors.insert(slot, color.with_alpha(u8::MAX));
+                }
+                Some(var) => {
+                    wrong_type.get_or_insert(EmitFault::SlotType {
+                        format,
+                        slot,
+                        got: var.value.describe(),
+                    });
+                }
+                None if OPTIONAL.contains(&slot) => {}
+                None => missing.push(slot),
+            }
+        }
+
+        if !missing.is_empty() {
+            return Err(EmitFault::MissingSlots { format, missing });
+        }
+        match wrong_type {
+            Some(fault) => Err(fault),
+            None => Ok(Slots { colors }),
+        }
+    }
+
+    fn get(&self, slot: &str) -> Option<String> {
+        self.colors.get(slot).map(|color| color.to_hex_string())
+    }
+
+    fn required(&self, slot: &str) -> String {
+        self.get(slot).expect("bug")
+    }
+
+    // 0から15の番号の色
+    fn indexed(&self) -> impl Iterator<Item = (usize, String)> + '_ {
+        ANSI.into_iter()
+            .chain(BRIGHT)
+            .enumerate()
+            .map(|(i, slot)| (i, self.required(slot)))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use crate::{
+        color::Color,
+        emit::{test::as_variables, EmitFault},
+        value::Value,
+    };
+
+    use super::{required_slots, Slots};
+
+    // 必須のslotを`#000000`, `#010101`, ... の順に定義する
+    pub(crate) fn full_theme() -> Vec<(&'static str, Value)> {
+        required_slots()
+            .enumerate()
+            .map(|(i, slot)| (slot, Value::Color(Color::new(i as u8, i as u8, i as u8))))
+            .collect()
+    }
+
+    #[test]
+    fn missing_slots() {
+        let mut values = full_theme();
+        values.retain(|(name, _)| *name != "red" && *name != "bright_white");
+        assert_eq!(
+            Slots::from_vars("Kitty", &as_variables(&values, &[])).err(),
+            Some(EmitFault::MissingSlots {
+                format: "Kitty",
+                missing: vec!["red", "bright_white"],
+            })
+        );
+
+        values.push(("red", Value::Number(1.0)));
+        values.push(("bright_white", Value::Color(Color::new(0, 0, 0))));
+        assert_eq!(
+            Slots::from_vars("Kitty", &as_variables(&values, &[])).err(),
+            Some(EmitFault::SlotType {
+                format: "Kitty",
+                slot: "red",
+                got: "number `1`".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn optional_slots() {
+        let mut values = full_theme();
+        values.push(("cursor", Value::Color(Color::new_rgba(1, 2, 3, 4))));
+
+        let slots = Slots::from_vars("Kitty", &as_variables(&values, &[])).unwrap();
+        assert_eq!(slots.get("cursor"), Some("#010203".to_string()));
+        assert_eq!(slots.get("cursor_text"), None);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::emit::{EmitFault, Emitter, Variable};
+
+use super::{Slots, ANSI, BRIGHT};
+
+// alacritty.toml 0.13以降のTOMLの形式
+pub struct AlacrittyEmitter;
+
+// (テーブル, キー, slot)
+const OPTIONAL_KEYS: [(&str, &str, &str); 4] = [
+    ("cursor", "cursor", "cursor"),
+    ("cursor", "text", "cursor_text"),
+    ("selection", "background", "selection_background"),
+    ("selection", "text", "selection_foreground"),
+];
+
+impl Emitter for AlacrittyEmitter {
+    fn emit(&self, vars: &[Variable]) -> Result<String, EmitFault> {
+        let slots = Slots::from_vars("Alacritty", vars)?;
+
+        let mut buf = String::from("[colors.primary]\n");
+        buf += &format!("background = \"{}\"\n", slots.required("background"));
+        buf += &format!("foreground = \"{}\"\n", slots.required("foreground"));
+
+        let mut table = "";
+        for (name, key, slot) in OPTIONAL_KEYS {
+            let Some(hex) = slots.get(slot) else {
+                continue;
+            };
+            if table != name {
+                buf += &format!("\n[colors.{}]\n", name);
+                table = name;
+            }
+            buf += &format!("{} = \"{}\"\n", key, hex);
+        }
+
+        for (name, names) in [("normal", ANSI), ("bright", BRIGHT)] {
+            buf += &format!("\n[colors.{}]\n", name);
+            for (key, slot) in ANSI.iter().zip(names) {
+                buf += &format!("{} = \"{}\"\n", key, slots.required(slot));
+            }
+        }
+
+        Ok(buf.trim_end().to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        color::Color,
+        emit::{terminal::test::full_theme, test::as_variables, Emitter},
+        value::Value,
+    };
+
+    use super::AlacrittyEmitter;
+
+    #[test]
+    fn emit() {
+        let mut values = full_theme();
+        values.push(("cursor", Value::Color(Color::new(255, 255, 255))));
+        let output = AlacrittyEmitter.emit(&as_variables(&values, &[])).unwrap();
+
+        assert!(output.starts_with(
+            "[colors.primary]\nbackground = \"#010101\"\nforeground = \"#000000\"\n\n[colors.cursor]\ncursor = \"#ffffff\"\n\n[colors.normal]\nblack = \"#020202\"\n"
+        ));
+        assert!(output.ends_with("[colors.bright]\nblack = \"#0a0a0a\"\nred = \"#0b0b0b\"\ngreen = \"#0c0c0c\"\nyellow = \"#0d0d0d\"\nblue = \"#0e0e0e\"\nmagenta = \"#0f0f0f\"\ncyan = \"#101010\"\nwhite = \"#111111\""));
+    }
+}
@@ -0,0 +1,50 @@
+use crate::emit::{EmitFault, Emitter, Variable};
+
+use super::Slots;
+
+// kitty.confにincludeする形式
+pub struct KittyEmitter;
+
+// (キー, slot)
+const KEYS: [(&str, &str); 6] = [
+    ("foreground", "foreground"),
+    ("background", "background"),
+    ("cursor", "cursor"),
+    ("cursor_text_color", "cursor_text"),
+    ("selection_foreground", "selection_foreground"),
+    ("selection_background", "selection_background"),
+];
+
+impl Emitter for KittyEmitter {
+    fn emit(&self, vars: &[Variable]) -> Result<String, EmitFault> {
+        let slots = Slots::from_vars("Kitty", vars)?;
+
+        let mut lines = Vec::new();
+        for (key, slot) in KEYS {
+            if let Some(hex) = slots.get(slot) {
+                lines.push(format!("{} {}", key, hex));
+            }
+        }
+        for (i, hex) in slots.indexed() {
+            lines.push(format!("color{} {}", i, hex));
+        }
+
+        Ok(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::emit::{terminal::test::full_theme, test::as_variables, Emitter};
+
+    use super::KittyEmitter;
+
+    #[test]
+    fn emit() {
+        let values = full_theme();
+        let output = KittyEmitter.emit(&as_variables(&values, &[])).unwrap();
+
+        assert!(output.starts_with("foreground #000000\nbackground #010101\ncolor0 #020202\n"));
+        assert!(output.ends_with("\ncolor15 #111111"));
+    }
+}
@@ -0,0 +1,60 @@
+use crate::emit::{EmitFault, Emitter, Variable};
+
+use super::{Slots, ANSI, BRIGHT};
+
+// ~/.config/wezterm/colors/に置くTOMLの配色
+pub struct WeztermEmitter;
+
+// (キー, slot) カーソルの枠はカーソルと同じ色にする
+const KEYS: [(&str, &str); 7] = [
+    ("foreground", "foreground"),
+    ("background", "background"),
+    ("cursor_bg", "cursor"),
+    ("cursor_border", "cursor"),
+    ("cursor_fg", "cursor_text"),
+    ("selection_bg", "selection_background"),
+    ("selection_fg", "selection_foreground"),
+];
+
+impl Emitter for WeztermEmitter {
+    fn emit(&self, vars: &[Variable]) -> Result<String, EmitFault> {
+        let slots = Slots::from_vars("WezTerm", vars)?;
+
+        let mut buf = String::from("[colors]\n");
+        for (key, slot) in KEYS {
+            if let Some(hex) = slots.get(slot) {
+                buf += &format!("{} = \"{}\"\n", key, hex);
+            }
+        }
+        for (key, names) in [("ansi", ANSI), ("brights", BRIGHT)] {
+            let colors: Vec<String> = names
+                .iter()
+                .map(|slot| format!("\"{}\"", slots.required(slot)))
+                .collect();
+            buf += &format!("{} = [{}]\n", key, colors.join(", "));
+        }
+
+        Ok(buf.trim_end().to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        color::Color,
+        emit::{terminal::test::full_theme, test::as_variables, Emitter},
+        value::Value,
+    };
+
+    use super::WeztermEmitter;
+
+    #[test]
+    fn emit() {
+        let mut values = full_theme();
+        values.push(("cursor", Value::Color(Color::new(255, 255, 255))));
+        let output = WeztermEmitter.emit(&as_variables(&values, &[])).unwrap();
+
+        assert!(output.starts_with("[colors]\nforeground = \"#000000\"\nbackground = \"#010101\"\ncursor_bg = \"#ffffff\"\ncursor_border = \"#ffffff\"\nansi = [\"#020202\", \"#030303\","));
+        assert!(output.ends_with("\"#101010\", \"#111111\"]"));
+    }
+}
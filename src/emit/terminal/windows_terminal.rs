@@ -0,0 +1,68 @@
+use serde_json::{json, Map};
+
+use crate::emit::{EmitFault, Emitter, Variable};
+
+use super::{Slots, ANSI, BRIGHT};
+
+// settings.jsonの"schemes"に追加するオブジェクト
+pub struct WindowsTerminalEmitter {
+    // 配色の名前 プロファイルの"colorScheme"で指定する
+    pub name: String,
+}
+
+// (キー, slot)
+const KEYS: [(&str, &str); 4] = [
+    ("foreground", "foreground"),
+    ("background", "background"),
+    ("cursorColor", "cursor"),
+    ("selectionBackground", "selection_background"),
+];
+
+// Windows Terminalではmagentaがpurpleと呼ばれている
+const ANSI_KEYS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "purple", "cyan", "white",
+];
+
+impl Emitter for WindowsTerminalEmitter {
+    fn emit(&self, vars: &[Variable]) -> Result<String, EmitFault> {
+        let slots = Slots::from_vars("Windows Terminal", vars)?;
+
+        let mut scheme = Map::new();
+        scheme.insert("name".to_string(), json!(self.name));
+        for (key, slot) in KEYS {
+            if let Some(hex) = slots.get(slot) {
+                scheme.insert(key.to_string(), json!(hex));
+            }
+        }
+        for (key, slot) in ANSI_KEYS.iter().zip(ANSI) {
+            scheme.insert(key.to_string(), json!(slots.required(slot)));
+        }
+        for (key, slot) in ANSI_KEYS.iter().zip(BRIGHT) {
+            // brightBlack, brightRed, ...
+            let key = format!("bright{}{}", key[..1].to_uppercase(), &key[1..]);
+            scheme.insert(key, json!(slots.required(slot)));
+        }
+
+        Ok(serde_json::to_string_pretty(&scheme).expect("bug"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::emit::{terminal::test::full_theme, test::as_variables, Emitter};
+
+    use super::WindowsTerminalEmitter;
+
+    #[test]
+    fn emit() {
+        let values = full_theme();
+        let emitter = WindowsTerminalEmitter {
+            name: "Night \"Owl\"".to_string(),
+        };
+        let output = emitter.emit(&as_variables(&values, &[])).unwrap();
+
+        assert!(output.starts_with("{\n  \"name\": \"Night \\\"Owl\\\"\",\n  \"foreground\": \"#000000\",\n  \"background\": \"#010101\",\n  \"black\": \"#020202\",\n"));
+        assert!(output.contains("\n  \"purple\": \"#070707\",\n"));
+        assert!(output.ends_with("\n  \"brightPurple\": \"#0f0f0f\",\n  \"brightCyan\": \"#101010\",\n  \"brightWhite\": \"#111111\"\n}"));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::emit::{EmitFault, Emitter, Variable};
+
+use super::Slots;
+
+// ~/.Xresourcesの`*.color0: #000000`の形式
+pub struct XresourcesEmitter;
+
+// (リソース名, slot)
+// 選択範囲の色はxtermとURxvtのhighlightColor、highlightTextColorはURxvtだけ
+const KEYS: [(&str, &str); 6] = [
+    ("foreground", "foreground"),
+    ("background", "background"),
+    ("cursorColor", "cursor"),
+    ("cursorColor2", "cursor_text"),
+    ("highlightColor", "selection_background"),
+    ("highlightTextColor", "selection_foreground"),
+];
+
+impl Emitter for XresourcesEmitter {
+    fn emit(&self, vars: &[Variable]) -> Result<String, EmitFault> {
+        let slots = Slots::from_vars("Xresources", vars)?;
+
+        let mut lines = Vec::new();
+        for (key, slot) in KEYS {
+            if let Some(hex) = slots.get(slot) {
+                lines.push(format!("*.{}: {}", key, hex));
+            }
+        }
+        for (i, hex) in slots.indexed() {
+            lines.push(format!("*.color{}: {}", i, hex));
+        }
+
+        Ok(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        color::Color,
+        emit::{terminal::test::full_theme, test::as_variables, Emitter},
+        value::Value,
+    };
+
+    use super::XresourcesEmitter;
+
+    #[test]
+    fn emit() {
+        let values = full_theme();
+        let output = XresourcesEmitter.emit(&as_variables(&values, &[])).unwrap();
+
+        assert!(
+            output.starts_with("*.foreground: #000000\n*.background: #010101\n*.color0: #020202\n")
+        );
+        assert!(output.ends_with("\n*.color15: #111111"));
+
+        let mut values = full_theme();
+        values.push((
+            "selection_background",
+            Value::Color(Color::new(0x33, 0x33, 0x33)),
+        ));
+        values.push((
+            "selection_foreground",
+            Value::Color(Color::new(0xee, 0xee, 0xee)),
+        ));
+        let output = XresourcesEmitter.emit(&as_variables(&values, &[])).unwrap();
+
+        assert!(output.contains("\n*.highlightColor: #333333\n*.highlightTextColor: #eeeeee\n"));
+    }
+}
@@ -0,0 +1,231 @@
+use std::{collections::VecDeque, fs, io, path::Path, rc::Rc};
+
+use crate::{
+    app_path::AbsFilePath,
+    color::Color,
+    emit::{ColorschemeEmitter, EmitFault, Emitter, Variable},
+    envroiment::Envroiment,
+    fault::Fault,
+    highlight::Highlight,
+    location::Spanned,
+    run::run,
+    template::{render_template, TemplateFault},
+    value::Value,
+};
+
+fn abs_file_path(path: &Path) -> io::Result<AbsFilePath> {
+    path.to_str()
+        .and_then(|path| AbsFilePath::from_string(path).ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid file path"))
+}
+
+// defiroを組み込んで使うための入口
+// 評価中のエラーは途中で止めずにためておき、faultsで取り出す
+pub struct Engine {
+    env: Envroiment,
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Engine {
+            env: Envroiment::new(),
+        }
+    }
+
+    // 関数を登録する 同じ名前の組み込み関数より優先される
+    // Errで返したメッセージは呼び出した位置のエラーとして報告される
+    pub fn register_function<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, String> + 'static,
+    {
+        self.env
+            .set_host_function(name.to_string(), Rc::new(function));
+    }
+
+    // `gray50`や`steelblue3`などX11の色の名前も使えるようにする
+    // CSSと値が違う名前はCSSの値のまま
+    pub fn enable_x11_colors(&mut self) {
+        self.env.x11_colors = true;
+    }
+
+    // ファイルを介さずにソースを評価する
+    // エラー表示では`<input>`というファイル名になり、includeはカレントディレクトリから探す
+    // カレントディレクトリが取得できなければErr
+    pub fn eval_str(&mut self, source: &str) -> io::Result<()> {
+        let file_path = abs_file_path(&std::env::current_dir()?.join("<input>"))?;
+        self.eval_chars(source.chars().collect(), file_path)
+    }
+
+    pub fn eval_file(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let source = fs::read_to_string(&path)?;
+        let file_path = abs_file_path(&fs::canonicalize(&path)?)?;
+        self.eval_chars(source.chars().collect(), file_path)
+    }
+
+    fn eval_chars(&mut self, chars: VecDeque<char>, file_path: AbsFilePath) -> io::Result<()> {
+        // 評価中のファイルをもう一度評価しようとした場合だけErrになる
+        run(&mut self.env, chars, file_path)
+            .map_err(|fault| io::Error::new(io::ErrorKind::InvalidInput, fault.msg()))
+    }
+
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.env.get(&name.to_string())
+    }
+
+    pub fn get_color(&self, name: &str) -> Option<Color> {
+        match self.get(name)? {
+            Value::Color(color) => Some(color),
+            _ => None,
+        }
+    }
+
+    // パーセントは0から100の数値として返す
+    pub fn get_number(&self, name: &str) -> Option<f64> {
+        match self.get(name)? {
+            Value::Number(number) | Value::Percent(number) => Some(number),
+            _ => None,
+        }
+    }
+
+    pub fn get_string(&self, name: &str) -> Option<String> {
+        match self.get(name)? {
+            Value::Str(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn faults(&self) -> &[Box<dyn Fault>] {
+        &self.env.faults
+    }
+
+    // エラー表示用 faultのlocationのファイル名で探す
+    pub fn source(&self, file: &str) -> Option<&str> {
+        self.env.get_source(file)
+    }
+
+    pub fn to_json(&self, with_docs: bool) -> String {
+        self.env.vars_json(with_docs)
+    }
+
+    // 名前の自然順に並んだ変数
+    pub fn variables(&self) -> Vec<Variable<'_>> {
+        self.env.variables()
+    }
+
+    pub fn emit(&self, emitter: &dyn Emitter) -> Result<String, EmitFault> {
+        emitter.emit(&self.variables())
+    }
+
+    // `{{ name }}`を変数の値で置き換える 書き方はtemplateを参照
+    pub fn render_template(&self, template: &str) -> Result<String, Spanned<TemplateFault>> {
+        render_template(template, &self.variables())
+    }
+
+    // `highlight Normal.fg = fg;`で定義されたグループ 最初に定義された順
+    pub fn highlights(&self) -> &[Highlight] {
+        self.env.highlights()
+    }
+
+    pub fn emit_colorscheme(&self, emitter: &dyn ColorschemeEmitter) -> Result<String, EmitFault> {
+        emitter.emit(self.highlights())
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{color::Color, highlight::Highlight, value::Value};
+
+    use super::Engine;
+
+    #[test]
+    fn register_function() {
+        let mut engine = Engine::new();
+        engine.register_function("brand", |args| match args {
+            [Value::Str(name)] if name == "primary" => Ok(Value::Color(Color::new(0, 102, 204))),
+            [Value::Str(name)] => Err(format!("unknown brand `{}`", name)),
+            _ => Err("expected a brand name".to_string()),
+        });
+        engine.register_function("huge", |_| Ok(Value::Number(f64::INFINITY)));
+
+        engine
+            .eval_str(
+                "\
+            primary = brand(\"primary\");
+            light = lighten(brand(\"primary\"), 10);
+            name = upper(\"primary\");
+            step = 4 / 2;
+            missing = brand(\"secondary\");
+            infinite = huge();
+            ",
+            )
+            .unwrap();
+
+        assert_eq!(engine.get_color("primary"), Some(Color::new(0, 102, 204)));
+        assert!(engine.get_color("light").is_some());
+        assert_eq!(engine.get_string("name"), Some("PRIMARY".to_string()));
+        assert_eq!(engine.get_number("step"), Some(2.0));
+        assert_eq!(engine.get_color("step"), None);
+        assert_eq!(engine.get("missing"), None);
+        assert_eq!(engine.get("infinite"), None);
+
+        let faults = engine.faults();
+        assert_eq!(faults.len(), 2);
+        assert_eq!(
+            faults[0].msg(),
+            "EvalError: `brand` failed: unknown brand `secondary`"
+        );
+        assert_eq!(
+            faults[1].msg(),
+            "EvalError: The result of the calculation is not a finite number"
+        );
+        assert_eq!(faults[0].location().unwrap().file, "<input>");
+    }
+
+    #[test]
+    fn highlights() {
+        let mut engine = Engine::new();
+        engine
+            .eval_str(
+                "\
+            fg = #c0c0c0;
+            highlight Normal.fg = fg;
+            highlight Comment.fg = darken(fg, 20);
+            highlight Normal.bg = black;
+            highlight Comment.style = \"italic, bold\";
+            Faded.fg = fg;
+            highlight Comment.bg = 10;
+            highlight Comment.style = \"blink\";
+            ",
+            )
+            .unwrap();
+
+        assert_eq!(
+            engine.highlights(),
+            &[
+                Highlight {
+                    group: "Normal".to_string(),
+                    fg: Some(Color::new(0xc0, 0xc0, 0xc0)),
+                    bg: Some(Color::new(0, 0, 0)),
+                    ..Default::default()
+                },
+                Highlight {
+                    group: "Comment".to_string(),
+                    fg: Some(Color::new(0x8d, 0x8d, 0x8d)),
+                    style: vec!["italic", "bold"],
+                    ..Default::default()
+                },
+            ]
+        );
+        assert_eq!(engine.get_color("Faded.fg"), engine.get_color("fg"));
+
+        let faults: Vec<String> = engine.faults().iter().map(|fault| fault.msg()).collect();
+        assert_eq!(faults.len(), 2);
+        assert_eq!(faults[1], "EvalError: Unknown highlight style `blink`");
+    }
+}
@@ -1,26 +1,170 @@
-use std::collections::HashMap;
+use std::{cmp::Ordering, collections::HashMap, rc::Rc};
 
-use crate::{color::Color, fault};
+use crate::{
+    emit::{Emitter, JsonEmitter, Variable},
+    fault::{self, LocatedFault},
+    highlight::Highlight,
+    location::{Location, Spanned},
+    parser::FunctionStatement,
+    value::Value,
+};
 
 use self::include_file_stack::IncludeFileStack;
+pub use self::include_file_stack::IncludeFileStackFault;
 mod include_file_stack;
 
+// `.`で区切られた部分ごとに比較し、両方が数字の部分は数値として比較する
+// blue.50 < blue.100 < blue.900 となるようにするため
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let is_number = |part: &str| !part.is_empty() && part.chars().all(|ch| ch.is_ascii_digit());
+
+    let mut a_parts = a.split('.');
+    let mut b_parts = b.split('.');
+    loop {
+        let (a_part, b_part) = match (a_parts.next(), b_parts.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a_part), Some(b_part)) => (a_part, b_part),
+        };
+
+        let ord = if is_number(a_part) && is_number(b_part) {
+            let a_num = a_part.trim_start_matches('0');
+            let b_num = b_part.trim_start_matches('0');
+            a_num
+                .len()
+                .cmp(&b_num.len())
+                .then_with(|| a_num.cmp(b_num))
+                .then_with(|| a_part.cmp(b_part))
+        } else {
+            a_part.cmp(b_part)
+        };
+
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+}
+
+// Engine::register_functionで登録されたホスト側の関数
+// エラーはメッセージだけを返してもらい、呼び出した位置でEvalFaultにする
+pub type HostFunction = Rc<dyn Fn(&[Value]) -> Result<Value, String>>;
+
 pub struct Envroiment {
-    map: HashMap<String, Color>,
+    map: HashMap<String, Value>,
     pub faults: Vec<Box<dyn fault::Fault>>,
     pub include_file_stack: IncludeFileStack,
+    // エラー表示用に読み込んだファイルの中身を残しておく
+    sources: HashMap<String, String>,
+    // 変数の`///`コメント
+    docs: HashMap<String, String>,
+    // `fn`で定義された関数 includeしたファイルで定義されたものも含む
+    functions: HashMap<String, Rc<FunctionStatement>>,
+    // 呼び出し中の関数の引数 関数の中からは一番上のものだけが見える
+    scopes: Vec<HashMap<String, Value>>,
+    host_functions: HashMap<String, HostFunction>,
+    // X11の色の名前も使えるようにする
+    pub x11_colors: bool,
+    // `highlight Normal.fg = fg;`で定義されたグループ 最初に定義された順
+    highlights: Vec<Highlight>,
 }
 
 impl Envroiment {
-    pub fn set(&mut self, name: String, color: Color) {
-        self.map.insert(name, color);
+    pub fn set(&mut self, name: String, value: Value) {
+        self.map.insert(name, value);
     }
 
-    pub fn get(&self, name: &String) -> Option<Color> {
-        match self.map.get(name) {
-            Some(c) => Some(c.clone()),
-            None => None,
+    pub fn get(&self, name: &String) -> Option<Value> {
+        if let Some(value) = self.scopes.last().and_then(|scope| scope.get(name)) {
+            return Some(value.clone());
         }
+        self.map.get(name).cloned()
+    }
+
+    pub fn set_function(&mut self, function: FunctionStatement) {
+        self.functions
+            .insert(function.name.clone(), Rc::new(function));
+    }
+
+    pub fn get_function(&self, name: &str) -> Option<Rc<FunctionStatement>> {
+        self.functions.get(name).cloned()
+    }
+
+    pub fn set_host_function(&mut self, name: String, function: HostFunction) {
+        self.host_functions.insert(name, function);
+    }
+
+    pub fn get_host_function(&self, name: &str) -> Option<HostFunction> {
+        self.host_functions.get(name).cloned()
+    }
+
+    pub fn function_names(&self) -> impl Iterator<Item = &str> {
+        self.functions
+            .keys()
+            .chain(self.host_functions.keys())
+            .map(String::as_str)
+    }
+
+    pub fn push_scope(&mut self, scope: HashMap<String, Value>) {
+        self.scopes.push(scope);
+    }
+
+    pub fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    // 関数呼び出しの深さ
+    pub fn scope_depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    // なければ空のグループを追加する
+    pub fn highlight_mut(&mut self, group: &str) -> &mut Highlight {
+        let index = match self.highlights.iter().position(|hl| hl.group == group) {
+            Some(index) => index,
+            None => {
+                self.highlights.push(Highlight {
+                    group: group.to_string(),
+                    ..Default::default()
+                });
+                self.highlights.len() - 1
+            }
+        };
+        &mut self.highlights[index]
+    }
+
+    pub fn highlights(&self) -> &[Highlight] {
+        &self.highlights
+    }
+
+    pub fn set_doc(&mut self, name: String, doc: String) {
+        self.docs.insert(name, doc);
+    }
+
+    pub fn get_doc(&self, name: &str) -> Option<&str> {
+        self.docs.get(name).map(|doc| doc.as_str())
+    }
+
+    fn current_file_name(&self) -> String {
+        self.include_file_stack.get_current_file().display()
+    }
+
+    // 今読んでいるファイルの名前をつけて記録する
+    pub fn push_fault<F: fault::Fault + 'static>(&mut self, fault: Spanned<F>) {
+        let location = Location {
+            file: self.current_file_name(),
+            span: fault.span,
+        };
+        self.faults
+            .push(Box::new(LocatedFault::new(Box::new(fault.inner), location)));
+    }
+
+    pub fn add_current_source(&mut self, source: String) {
+        self.sources.insert(self.current_file_name(), source);
+    }
+
+    pub fn get_source(&self, file: &str) -> Option<&str> {
+        self.sources.get(file).map(|source| source.as_str())
     }
 
     pub fn new() -> Self {
@@ -28,24 +172,38 @@ impl Envroiment {
             map: HashMap::new(),
             faults: Vec::new(),
             include_file_stack: IncludeFileStack::new(),
+            sources: HashMap::new(),
+            docs: HashMap::new(),
+            functions: HashMap::new(),
+            scopes: Vec::new(),
+            host_functions: HashMap::new(),
+            x11_colors: false,
+            highlights: Vec::new(),
         }
     }
 
-    pub fn vars_json(&self) -> String {
-        let mut buf = String::new();
-        buf += "{";
-        let mut vars = Vec::new();
+    // 変数を名前の自然順に並べる blue.50 < blue.100 < blue.900
+    pub fn variables(&self) -> Vec<Variable<'_>> {
+        let mut vars: Vec<Variable> = self
+            .map
+            .iter()
+            .map(|(name, value)| Variable {
+                name,
+                value,
+                doc: self.get_doc(name),
+            })
+            .collect();
+        vars.sort_by(|a, b| natural_cmp(a.name, b.name));
+        vars
+    }
 
-        for var in &self.map {
-            vars.push(format!("\"{}\":\"{}\"", var.0, var.1.to_hex_string()));
+    pub fn vars_json(&self, with_docs: bool) -> String {
+        JsonEmitter {
+            with_docs,
+            ..Default::default()
         }
-
-        vars.sort();
-
-        buf += &vars.join(",");
-
-        buf += "}";
-        buf
+        .emit(&self.variables())
+        .expect("bug")
     }
 }
 
@@ -58,7 +216,7 @@ impl Envroiment {
 
 #[cfg(test)]
 mod test {
-    use crate::color::Color;
+    use crate::{color::Color, value::Value};
 
     use super::Envroiment;
 
@@ -95,6 +253,15 @@ mod test {
 
         test_vars_json(vec![], "{}");
 
+        // アルファ値が不透明でない場合のみ8桁
+        test_vars_json(
+            vec![
+                ("a", Color::new_rgba(20, 16, 255, 255)),
+                ("b", Color::new_rgba(20, 16, 255, 128)),
+            ],
+            "{\"a\":\"#1410ff\",\"b\":\"#1410ff80\"}",
+        );
+
         test_vars_json(
             vec![
                 ("a", Color::new(20, 16, 255)),
@@ -104,11 +271,61 @@ mod test {
         );
     }
 
+    #[test]
+    fn natural_order() {
+        test_vars_json(
+            vec![
+                ("blue.900", Color::new(0, 0, 0)),
+                ("blue.50", Color::new(0, 0, 0)),
+                ("blue.100", Color::new(0, 0, 0)),
+                ("blue", Color::new(0, 0, 0)),
+                ("blue.500", Color::new(0, 0, 0)),
+                ("blue.a", Color::new(0, 0, 0)),
+                ("aqua.900", Color::new(0, 0, 0)),
+            ],
+            "{\"aqua.900\":\"#000000\",\"blue\":\"#000000\",\"blue.50\":\"#000000\",\"blue.100\":\"#000000\",\"blue.500\":\"#000000\",\"blue.900\":\"#000000\",\"blue.a\":\"#000000\"}",
+        );
+
+        // 区切られていない数字は今まで通り辞書順
+        test_vars_json(
+            vec![
+                ("hello10", Color::new(0, 0, 0)),
+                ("hello9", Color::new(0, 0, 0)),
+            ],
+            "{\"hello10\":\"#000000\",\"hello9\":\"#000000\"}",
+        );
+    }
+
+    #[test]
+    fn vars_json_numbers() {
+        let mut env = Envroiment::new();
+        env.set("a".to_string(), Value::Number(10.0));
+        env.set("b".to_string(), Value::Number(0.5));
+        env.set("c".to_string(), Value::Percent(50.0));
+        env.set("d".to_string(), Value::Color(Color::new(0, 0, 0)));
+        assert_eq!(
+            env.vars_json(false),
+            "{\"a\":10,\"b\":0.5,\"c\":\"50%\",\"d\":\"#000000\"}"
+        );
+    }
+
+    #[test]
+    fn vars_json_with_docs() {
+        let mut env = Envroiment::new();
+        env.set("a".to_string(), Value::Color(Color::new(0, 0, 0)));
+        env.set("b".to_string(), Value::Color(Color::new(255, 255, 255)));
+        env.set_doc("a".to_string(), "Background\n\"dark\"".to_string());
+        assert_eq!(
+            env.vars_json(true),
+            "{\"a\":{\"value\":\"#000000\",\"description\":\"Background\\n\\\"dark\\\"\"},\"b\":{\"value\":\"#ffffff\",\"description\":null}}"
+        );
+    }
+
     fn test_vars_json(vars: Vec<(&str, Color)>, json: &str) {
         let mut env = Envroiment::new();
         for v in vars {
-            env.set(v.0.to_string(), v.1);
+            env.set(v.0.to_string(), Value::Color(v.1));
         }
-        assert_eq!(env.vars_json(), json);
+        assert_eq!(env.vars_json(false), json);
     }
 }
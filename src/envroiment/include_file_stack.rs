@@ -1,6 +1,6 @@
 use crate::{app_path::AbsFilePath, fault};
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct IncludeFileStackFault {
     path: String,
 }
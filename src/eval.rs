@@ -1,36 +1,99 @@
 // use std::fs;
 
+pub mod builtin;
 mod function;
+mod operator;
 
 use std::fs::read_to_string;
+use std::rc::Rc;
 
 use crate::app_path::{self, AbsFilePathError};
-use crate::color::ColorInt;
-use crate::envroiment::Envroiment;
-use crate::parser::Expression;
+use crate::envroiment::{Envroiment, IncludeFileStackFault};
+use crate::highlight::{parse_style, HighlightAttr, STYLES};
+use crate::location::{Span, Spanned};
+use crate::named_color::named_color;
+use crate::parser::{BinaryOp, Expression, ExpressionKind};
+use crate::utils::{closest_name, round_for_display};
+use crate::value::Value;
 use crate::{
-    color::Color,
     fault,
-    parser::{Call, IncludeStatement, LetStatement, Statement},
+    parser::{
+        AssertStatement, Call, CompareOp, FunctionStatement, HighlightStatement, IncludeStatement,
+        LetStatement, PaletteStatement, Statement, StatementKind,
+    },
     run::run,
 };
 
-use self::function::{eval_minus_function, eval_plus_function, eval_rgb_function};
+use self::builtin::{find_builtin, BUILTINS};
+use self::function::{eval_args, ramp, Arg};
+use self::operator::{check_finite, eval_binary_operation, eval_negate};
 
 #[derive(Debug, PartialEq)]
 pub enum EvalFault {
-    NotFound { target_name: String },
-    NoSuchFile { path: String },
-    TodoRename2 { err: AbsFilePathError },
-    IsNotFunction { target_name: String },
-    NumArgments { req: usize, got: usize },
-    ArgType, // { req: String, got: String },
-}
-
-#[derive(Debug, PartialEq)]
-enum Value {
-    Color(Color),
-    Int(ColorInt),
+    NotFound {
+        target_name: String,
+    },
+    NoSuchFile {
+        path: String,
+    },
+    TodoRename2 {
+        err: AbsFilePathError,
+    },
+    IsNotFunction {
+        target_name: String,
+        suggestion: Option<String>,
+    },
+    NumArgments {
+        req: usize,
+        got: usize,
+    },
+    ArgType {
+        param: &'static str,
+        req: String,
+        got: String,
+    },
+    TypeMismatch {
+        req: &'static str,
+        got: String,
+    },
+    AssertionFailed {
+        left: f64,
+        op: CompareOp,
+        right: f64,
+    },
+    PaletteAssign {
+        target_name: String,
+    },
+    RecursiveInclude {
+        fault: IncludeFileStackFault,
+    },
+    InvalidOperands {
+        op: BinaryOp,
+        left: &'static str,
+        right: &'static str,
+    },
+    InvalidNegation {
+        operand: &'static str,
+    },
+    DivisionByZero,
+    NotFinite,
+    RecursionLimit {
+        name: String,
+    },
+    // ユーザー定義関数の中で起きたエラー 呼び出した位置で報告する
+    InFunction {
+        name: String,
+        fault: Box<EvalFault>,
+    },
+    // `highlight Normal.style = "blink"`
+    UnknownStyle {
+        style: String,
+    },
+    // ホスト側で登録された関数が返したエラー
+    HostFunction {
+        name: String,
+        msg: String,
+    },
 }
 
 impl From<AbsFilePathError> for EvalFault {
@@ -49,7 +112,7 @@ impl fault::Fault for EvalFault {
                 format!("EvalError: No such file. path:{}", path)
             }
             EvalFault::TodoRename2 { err } => format!("EvalError: {:?}", err),
-            EvalFault::IsNotFunction { target_name } => {
+            EvalFault::IsNotFunction { target_name, .. } => {
                 format!("EvalError: {} is not function", target_name)
             }
             EvalFault::NumArgments { req, got } => {
@@ -58,12 +121,118 @@ impl fault::Fault for EvalFault {
                     req, got
                 )
             }
-            // EvalFault::ArgType { req, got } => {
-            //     format!("EvalError: The type of the argument is differentent. req={} got={}",req,got)
-            // }
-            EvalFault::ArgType => {
-                format!("EvalError: The type of the argument is differentent.")
+            EvalFault::AssertionFailed { left, op, right } => {
+                format!(
+                    "EvalError: Assertion failed. {} {} {}",
+                    round_for_display(*left),
+                    op.symbol(),
+                    round_for_display(*right)
+                )
+            }
+            EvalFault::PaletteAssign { target_name } => {
+                format!(
+                    "EvalError: Use `palette {} = ...` to define a palette",
+                    target_name
+                )
+            }
+            EvalFault::RecursiveInclude { fault } => fault.msg(),
+            EvalFault::InvalidOperands { op, left, right } => {
+                format!(
+                    "EvalError: Cannot apply `{}` to {} and {}",
+                    op.symbol(),
+                    left,
+                    right
+                )
+            }
+            EvalFault::InvalidNegation { operand } => {
+                format!("EvalError: Cannot negate {}", operand)
+            }
+            EvalFault::DivisionByZero => "EvalError: Division by zero".to_string(),
+            EvalFault::NotFinite => {
+                "EvalError: The result of the calculation is not a finite number".to_string()
+            }
+            EvalFault::RecursionLimit { name } => {
+                format!(
+                    "EvalError: Too many nested function calls in `{}`. limit={}",
+                    name, MAX_CALL_DEPTH
+                )
+            }
+            EvalFault::UnknownStyle { style } => {
+                format!("EvalError: Unknown highlight style `{}`", style)
+            }
+            EvalFault::HostFunction { name, msg } => {
+                format!("EvalError: `{}` failed: {}", name, msg)
+            }
+            EvalFault::InFunction { name, fault } => {
+                format!("{} (in function `{}`)", fault.msg(), name)
+            }
+            EvalFault::ArgType { param, req, got } => {
+                format!(
+                    "EvalError: Wrong type of argument `{}`. expected {}, found {}",
+                    param, req, got
+                )
+            }
+            EvalFault::TypeMismatch { req, got } => {
+                format!(
+                    "EvalError: Mismatched types. expected {}, found {}",
+                    req, got
+                )
+            }
+        }
+    }
+
+    fn label(&self) -> Option<String> {
+        match self {
+            EvalFault::NotFound { .. } => Some("not defined".to_string()),
+            EvalFault::NoSuchFile { .. } => Some("cannot read this file".to_string()),
+            EvalFault::IsNotFunction { .. } => Some("unknown function".to_string()),
+            EvalFault::NumArgments { req, .. } => Some(format!("expected {} arguments", req)),
+            EvalFault::ArgType { req, .. } => Some(format!("expected {}", req)),
+            EvalFault::TypeMismatch { req, .. } => Some(format!("expected {}", req)),
+            EvalFault::AssertionFailed { .. } => Some("assertion failed".to_string()),
+            EvalFault::RecursiveInclude { .. } => Some("included here".to_string()),
+            EvalFault::InvalidOperands { left, right, .. } => {
+                Some(format!("{} and {}", left, right))
+            }
+            EvalFault::InvalidNegation { operand } => Some(operand.to_string()),
+            EvalFault::RecursionLimit { .. } => Some("called here".to_string()),
+            EvalFault::InFunction { name, .. } | EvalFault::HostFunction { name, .. } => {
+                Some(format!("error in this call to `{}`", name))
+            }
+            _ => None,
+        }
+    }
+
+    fn help(&self) -> Option<String> {
+        match self {
+            // `b-c`は1つの名前になる
+            EvalFault::NotFound { target_name } => match target_name.split_once('-') {
+                Some((left, right)) if !left.is_empty() && !right.is_empty() => Some(format!(
+                    "to subtract, use spaces around `-`, e.g. `{} - {}`",
+                    left, right
+                )),
+                _ => Some("variables must be defined before they are used".to_string()),
+            },
+            EvalFault::PaletteAssign { target_name } => Some(format!(
+                "palette entries are named `{}.50`, `{}.100`, ...",
+                target_name, target_name
+            )),
+            EvalFault::InvalidOperands { .. } => Some(
+                "colors can be added to or subtracted from each other, and combined with numbers"
+                    .to_string(),
+            ),
+            EvalFault::RecursionLimit { .. } => {
+                Some("functions cannot call themselves recursively".to_string())
+            }
+            EvalFault::InFunction { fault, .. } => fault.help(),
+            EvalFault::UnknownStyle { .. } => {
+                Some(format!("available styles are {}", STYLES.join(", ")))
             }
+            EvalFault::IsNotFunction {
+                suggestion: Some(suggestion),
+                ..
+            } => Some(format!("did you mean `{}`?", suggestion)),
+            _ => None,
         }
     }
 }
@@ -83,54 +252,295 @@ pub fn eval_include_stmt(
         }
     };
 
-    run(env, file_string.chars().collect(), file_path);
+    if let Err(fault) = run(env, file_string.chars().collect(), file_path) {
+        return Err(EvalFault::RecursiveInclude { fault });
+    }
 
     Ok(())
 }
 
-pub fn eval(stmt: Statement, env: &mut Envroiment) -> Result<(), EvalFault> {
-    match stmt {
-        Statement::Let(let_stmt) => eval_let_statement(let_stmt, env),
-        Statement::Include(include_stmt) => eval_include_stmt(include_stmt, env),
+pub fn eval(stmt: Statement, env: &mut Envroiment) -> Result<(), Spanned<EvalFault>> {
+    let span = stmt.span;
+    match stmt.kind {
+        StatementKind::Let(let_stmt) => eval_let_statement(let_stmt, stmt.doc, span, env),
+        StatementKind::Include(include_stmt) => {
+            eval_include_stmt(include_stmt, env).map_err(|fault| Spanned::new(fault, span))
+        }
+        StatementKind::Assert(assert_stmt) => eval_assert_statement(assert_stmt, span, env),
+        StatementKind::Palette(palette_stmt) => eval_palette_statement(palette_stmt, stmt.doc, env),
+        StatementKind::Highlight(highlight_stmt) => eval_highlight_statement(highlight_stmt, env),
+        StatementKind::Function(function_stmt) => {
+            env.set_function(function_stmt);
+            Ok(())
+        }
     }
 }
 
-fn eval_let_statement(let_stmt: LetStatement, env: &mut Envroiment) -> Result<(), EvalFault> {
+// palette blue = ramp(base, 10) で blue.50 ... blue.900 を定義する
+// 右辺が色の場合は ramp(color, 10) と同じ
+fn eval_palette_statement(
+    palette_stmt: PaletteStatement,
+    doc: Option<String>,
+    env: &mut Envroiment,
+) -> Result<(), Spanned<EvalFault>> {
+    let right_span = palette_stmt.right.span;
+    let palette = match eval_expression(palette_stmt.right, env)? {
+        Value::Palette(palette) => palette,
+        Value::Color(color) => ramp(&color, 10),
+        value => {
+            return Err(Spanned::new(
+                EvalFault::TypeMismatch {
+                    req: "a palette or a color",
+                    got: value.describe(),
+                },
+                right_span,
+            ))
+        }
+    };
+
+    // ドキュメントはすべての段階に同じものをつける
+    for (step, color) in palette {
+        let name = format!("{}.{}", palette_stmt.name, step);
+        if let Some(doc) = &doc {
+            env.set_doc(name.clone(), doc.clone());
+        }
+        env.set(name, Value::Color(color));
+    }
+    Ok(())
+}
+
+fn eval_assert_statement(
+    assert_stmt: AssertStatement,
+    span: Span,
+    env: &mut Envroiment,
+) -> Result<(), Spanned<EvalFault>> {
+    let left = eval_number_expression(assert_stmt.left, env)?;
+    let right = eval_number_expression(assert_stmt.right, env)?;
+
+    if assert_stmt.op.compare(left, right) {
+        Ok(())
+    } else {
+        Err(Spanned::new(
+            EvalFault::AssertionFailed {
+                left,
+                op: assert_stmt.op,
+                right,
+            },
+            span,
+        ))
+    }
+}
+
+fn eval_let_statement(
+    let_stmt: LetStatement,
+    doc: Option<String>,
+    span: Span,
+    env: &mut Envroiment,
+) -> Result<(), Spanned<EvalFault>> {
     let value = eval_expression(let_stmt.right, env)?;
-    let Value::Color(color) = value else { todo!() };
+    if let Value::Palette(_) = value {
+        return Err(Spanned::new(
+            EvalFault::PaletteAssign {
+                target_name: let_stmt.left,
+            },
+            span,
+        ));
+    }
 
-    env.set(let_stmt.left, color);
+    if let Some(doc) = doc {
+        env.set_doc(let_stmt.left.clone(), doc);
+    }
+    env.set(let_stmt.left, value);
     Ok(())
 }
 
-fn eval_identifer(name: String, env: &mut Envroiment) -> Result<Value, EvalFault> {
-    match env.get(&name) {
+fn eval_identifer(
+    name: String,
+    span: Span,
+    env: &mut Envroiment,
+) -> Result<Value, Spanned<EvalFault>> {
+    if let Some(value) = env.get(&name) {
+        return Ok(value);
+    }
+
+    // 同じ名前の変数がなければ`red`などの色の名前として扱う
+    match named_color(&name, env.x11_colors) {
         Some(color) => Ok(Value::Color(color)),
-        None => Err(EvalFault::NotFound { target_name: name }),
+        None => Err(Spanned::new(
+            EvalFault::NotFound { target_name: name },
+            span,
+        )),
     }
 }
 
-fn eval_call(call: Call, env: &mut Envroiment) -> Result<Value, EvalFault> {
-    if call.name == "plus" {
-        eval_plus_function(call.args, env)
-    } else if call.name == "minus" {
-        eval_minus_function(call.args, env)
-    } else if call.name == "rgb" {
-        eval_rgb_function(call.args)
-    } else {
-        Err(EvalFault::IsNotFunction {
-            target_name: call.name,
-        })
+// fg bg spは色、styleは"bold,italic"のような文字列
+// エラーの場合はグループを作らない
+fn eval_highlight_statement(
+    highlight_stmt: HighlightStatement,
+    env: &mut Envroiment,
+) -> Result<(), Spanned<EvalFault>> {
+    let span = highlight_stmt.value.span;
+    let value = eval_expression(highlight_stmt.value, env)?;
+    let attr = highlight_stmt.attr;
+
+    let type_mismatch = |req, value: Value| {
+        Spanned::new(
+            EvalFault::TypeMismatch {
+                req,
+                got: value.describe(),
+            },
+            span,
+        )
+    };
+    if attr == HighlightAttr::Style {
+        let style = match value {
+            Value::Str(text) => parse_style(&text)
+                .map_err(|style| Spanned::new(EvalFault::UnknownStyle { style }, span))?,
+            value => return Err(type_mismatch("a string", value)),
+        };
+        env.highlight_mut(&highlight_stmt.group).style = style;
+        return Ok(());
+    }
+
+    let color = match value {
+        Value::Color(color) => color,
+        value => return Err(type_mismatch("a color", value)),
+    };
+    let highlight = env.highlight_mut(&highlight_stmt.group);
+    match attr {
+        HighlightAttr::Fg => highlight.fg = Some(color),
+        HighlightAttr::Bg => highlight.bg = Some(color),
+        HighlightAttr::Sp => highlight.sp = Some(color),
+        HighlightAttr::Style => unreachable!(),
     }
+    Ok(())
 }
 
-fn eval_expression(exp: Expression, env: &mut Envroiment) -> Result<Value, EvalFault> {
-    let value = match exp {
-        Expression::Color(color) => Value::Color(color),
-        Expression::Identifier(name) => eval_identifer(name, env)?,
-        Expression::Call(call) => eval_call(call, env)?,
-        Expression::Int(int) => Value::Int(int),
+// 関数が自分自身を呼び出し続ける場合に止める
+const MAX_CALL_DEPTH: usize = 64;
+
+// 引数は呼び出し元で評価し、関数の中では引数と変数だけが見える
+fn eval_user_function(
+    function: Rc<FunctionStatement>,
+    args: Vec<Arg>,
+    span: Span,
+    env: &mut Envroiment,
+) -> Result<Value, Spanned<EvalFault>> {
+    if args.len() != function.params.len() {
+        return Err(Spanned::new(
+            EvalFault::NumArgments {
+                req: function.params.len(),
+                got: args.len(),
+            },
+            span,
+        ));
+    }
+    if env.scope_depth() >= MAX_CALL_DEPTH {
+        return Err(Spanned::new(
+            EvalFault::RecursionLimit {
+                name: function.name.clone(),
+            },
+            span,
+        ));
+    }
+
+    let scope = function
+        .params
+        .iter()
+        .cloned()
+        .zip(args.into_iter().map(|arg| arg.inner))
+        .collect();
+    env.push_scope(scope);
+    let result = eval_expression(function.body.clone(), env);
+    env.pop_scope();
+
+    result.map_err(|fault| {
+        // 入れ子の呼び出しでは一番内側の関数名を残す
+        let fault = match fault.inner {
+            fault @ (EvalFault::InFunction { .. } | EvalFault::RecursionLimit { .. }) => fault,
+            fault => EvalFault::InFunction {
+                name: function.name.clone(),
+                fault: Box::new(fault),
+            },
+        };
+        Spanned::new(fault, span)
+    })
+}
+
+fn eval_call(call: Call, span: Span, env: &mut Envroiment) -> Result<Value, Spanned<EvalFault>> {
+    let args = eval_args(call.args, env)?;
+
+    // ユーザー定義の関数は同じ名前の組み込み関数より優先する
+    if let Some(function) = env.get_function(&call.name) {
+        return eval_user_function(function, args, span, env);
+    }
+
+    // ホスト側で登録された関数は組み込み関数より優先する
+    if let Some(function) = env.get_host_function(&call.name) {
+        let values: Vec<Value> = args.into_iter().map(|arg| arg.inner).collect();
+        let value = function(&values).map_err(|msg| {
+            Spanned::new(
+                EvalFault::HostFunction {
+                    name: call.name,
+                    msg,
+                },
+                span,
+            )
+        })?;
+        return check_finite(value).map_err(|fault| Spanned::new(fault, span));
+    }
+
+    if let Some(builtin) = find_builtin(&call.name) {
+        return builtin.call(args, span);
+    }
+
+    let builtin_names = BUILTINS.iter().map(|builtin| builtin.name);
+    let suggestion = closest_name(&call.name, builtin_names.chain(env.function_names()));
+    Err(Spanned::new(
+        EvalFault::IsNotFunction {
+            suggestion: suggestion.map(str::to_string),
+            target_name: call.name,
+        },
+        span,
+    ))
+}
+
+fn eval_expression(exp: Expression, env: &mut Envroiment) -> Result<Value, Spanned<EvalFault>> {
+    let value = match exp.kind {
+        ExpressionKind::Color(color) => Value::Color(color),
+        ExpressionKind::Identifier(name) => eval_identifer(name, exp.span, env)?,
+        ExpressionKind::Call(call) => eval_call(call, exp.span, env)?,
+        ExpressionKind::Number(number) => Value::Number(number),
+        ExpressionKind::Percent(number) => Value::Percent(number),
+        ExpressionKind::Str(text) => Value::Str(text),
+        ExpressionKind::Binary { op, left, right } => {
+            let left = eval_expression(*left, env)?;
+            let right = eval_expression(*right, env)?;
+            eval_binary_operation(op, left, right).map_err(|fault| Spanned::new(fault, exp.span))?
+        }
+        ExpressionKind::Negate(operand) => {
+            let operand = eval_expression(*operand, env)?;
+            eval_negate(operand).map_err(|fault| Spanned::new(fault, exp.span))?
+        }
     };
 
     Ok(value)
 }
+
+fn eval_number_expression(
+    exp: Expression,
+    env: &mut Envroiment,
+) -> Result<f64, Spanned<EvalFault>> {
+    let span = exp.span;
+    match eval_expression(exp, env)? {
+        // `assert saturation(c) >= 50%` のように比べられるようにする
+        Value::Number(number) | Value::Percent(number) => Ok(number),
+        value => Err(Spanned::new(
+            EvalFault::TypeMismatch {
+                req: "a number",
+                got: value.describe(),
+            },
+            span,
+        )),
+    }
+}
@@ -0,0 +1,538 @@
+use std::fmt;
+
+use crate::{
+    color::{BlendMode, Color, ColorInt},
+    location::{Span, Spanned},
+};
+
+use super::function::{
+    eval_adjust_function, eval_blend_function, eval_channel_function, eval_color_space_function,
+    eval_concat_function, eval_contrast_function, eval_format_function, eval_hsl_function,
+    eval_hsv_function, eval_luminance_function, eval_minus_function, eval_mix_function,
+    eval_nearest_name_function, eval_over_function, eval_plus_function, eval_ramp_function,
+    eval_rgb_function, eval_rgba_function, eval_scale_function, eval_upper_function, Adjustment,
+    Arg, Channel, ColorSpace,
+};
+use super::{EvalFault, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParamType {
+    Number,
+    Color,
+    // 0から255の整数 計算結果の小数は四捨五入する
+    // CSSと同じく`50%`は255の50%になる
+    Channel,
+    // 0から100のパーセント `20`と`20%`はどちらも20%
+    Percent,
+    Integer { min: u32, max: u32 },
+    Str,
+    // 文字列として使える値 数値はそのまま、色は`#rrggbb`にする
+    Text,
+}
+
+impl ParamType {
+    fn name(&self) -> &'static str {
+        match self {
+            ParamType::Number => "number",
+            ParamType::Color => "color",
+            ParamType::Channel => "channel",
+            ParamType::Percent => "percentage",
+            ParamType::Integer { .. } => "integer",
+            ParamType::Str => "string",
+            ParamType::Text => "text",
+        }
+    }
+
+    fn req(&self) -> String {
+        match self {
+            ParamType::Number => "a number".to_string(),
+            ParamType::Color => "a color".to_string(),
+            ParamType::Channel => "an integer from 0 to 255".to_string(),
+            ParamType::Percent => "a percentage from 0 to 100".to_string(),
+            ParamType::Integer { min, max } => format!("an integer from {} to {}", min, max),
+            ParamType::Str => "a string".to_string(),
+            ParamType::Text => "a string, number or color".to_string(),
+        }
+    }
+
+    // 関数の実装が使う形に変換する
+    // チャンネルは整数、パーセントは0から1の割合、テキストは文字列になる
+    fn coerce(&self, value: &Value) -> Option<Value> {
+        match (self, value) {
+            (ParamType::Number, Value::Number(number)) => Some(Value::Number(*number)),
+            (ParamType::Color, Value::Color(color)) => Some(Value::Color(color.clone())),
+            (ParamType::Channel, Value::Number(number) | Value::Percent(number)) => {
+                let number = match value {
+                    Value::Percent(_) => number / 100.0 * ColorInt::MAX as f64,
+                    _ => *number,
+                };
+                let number = number.round();
+                (0.0..=ColorInt::MAX as f64)
+                    .contains(&number)
+                    .then_some(Value::Number(number))
+            }
+            (ParamType::Percent, Value::Number(number) | Value::Percent(number)) => (0.0..=100.0)
+                .contains(number)
+                .then_some(Value::Number(number / 100.0)),
+            (ParamType::Integer { min, max }, Value::Number(number)) => {
+                let in_range = (*min as f64..=*max as f64).contains(number);
+                (number.fract() == 0.0 && in_range).then_some(Value::Number(*number))
+            }
+            (ParamType::Str, Value::Str(text)) => Some(Value::Str(text.clone())),
+            (ParamType::Text, Value::Str(text)) => Some(Value::Str(text.clone())),
+            (ParamType::Text, Value::Number(number)) => Some(Value::Str(number.to_string())),
+            (ParamType::Text, Value::Percent(number)) => Some(Value::Str(format!("{}%", number))),
+            (ParamType::Text, Value::Color(color)) => Some(Value::Str(color.to_hex_string())),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Param {
+    pub name: &'static str,
+    pub ty: ParamType,
+}
+
+impl Param {
+    const fn new(name: &'static str, ty: ParamType) -> Self {
+        Param { name, ty }
+    }
+}
+
+// 型のチェックと変換が済んだ引数
+#[derive(Debug)]
+pub(super) struct Args {
+    values: Vec<Value>,
+}
+
+impl Args {
+    pub(super) fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub(super) fn get(&self, index: usize) -> Option<&Value> {
+        self.values.get(index)
+    }
+
+    pub(super) fn number(&self, index: usize) -> f64 {
+        let Some(Value::Number(number)) = self.get(index) else {
+            panic!("bug: argument {} is not a number", index);
+        };
+        *number
+    }
+
+    pub(super) fn channel(&self, index: usize) -> ColorInt {
+        self.number(index) as ColorInt
+    }
+
+    pub(super) fn color(&self, index: usize) -> &Color {
+        let Some(Value::Color(color)) = self.get(index) else {
+            panic!("bug: argument {} is not a color", index);
+        };
+        color
+    }
+
+    pub(super) fn string(&self, index: usize) -> &str {
+        let Some(Value::Str(text)) = self.get(index) else {
+            panic!("bug: argument {} is not a string", index);
+        };
+        text
+    }
+}
+
+pub struct Builtin {
+    pub name: &'static str,
+    pub params: &'static [Param],
+    // 後ろから何個の引数を省略できるか
+    pub optional: usize,
+    // 最後の引数を何個でも渡せる
+    pub variadic: bool,
+    pub returns: &'static str,
+    func: fn(&Args) -> Result<Value, EvalFault>,
+}
+
+impl Builtin {
+    fn min_args(&self) -> usize {
+        self.params.len() - self.optional
+    }
+
+    fn max_args(&self) -> Option<usize> {
+        (!self.variadic).then_some(self.params.len())
+    }
+
+    // 引数の数が違う場合は関数呼び出し全体、型が違う場合はその引数の位置でエラーにする
+    pub(super) fn call(&self, args: Vec<Arg>, span: Span) -> Result<Value, Spanned<EvalFault>> {
+        let got = args.len();
+        let req = if got < self.min_args() {
+            Some(self.min_args())
+        } else {
+            self.max_args().filter(|max| got > *max)
+        };
+        if let Some(req) = req {
+            return Err(Spanned::new(EvalFault::NumArgments { req, got }, span));
+        }
+
+        let mut values = Vec::with_capacity(got);
+        for (i, arg) in args.iter().enumerate() {
+            let param = &self.params[i.min(self.params.len() - 1)];
+            let Some(value) = param.ty.coerce(&arg.inner) else {
+                return Err(Spanned::new(
+                    EvalFault::ArgType {
+                        param: param.name,
+                        req: param.ty.req(),
+                        got: arg.inner.describe(),
+                    },
+                    arg.span,
+                ));
+            };
+            values.push(value);
+        }
+
+        (self.func)(&Args { values }).map_err(|fault| Spanned::new(fault, span))
+    }
+}
+
+// plus(color: color, r: channel, ..., a?: channel) -> color
+impl fmt::Display for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}(", self.name)?;
+        for (i, param) in self.params.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            let optional = if i >= self.min_args() { "?" } else { "" };
+            write!(f, "{}{}: {}", param.name, optional, param.ty.name())?;
+        }
+        if self.variadic {
+            write!(f, "...")?;
+        }
+        write!(f, ") -> {}", self.returns)
+    }
+}
+
+pub fn find_builtin(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+const fn fixed(
+    name: &'static str,
+    params: &'static [Param],
+    returns: &'static str,
+    func: fn(&Args) -> Result<Value, EvalFault>,
+) -> Builtin {
+    Builtin {
+        name,
+        params,
+        optional: 0,
+        variadic: false,
+        returns,
+        func,
+    }
+}
+
+const COLOR: Param = Param::new("color", ParamType::Color);
+const RGB: [Param; 3] = [
+    Param::new("r", ParamType::Channel),
+    Param::new("g", ParamType::Channel),
+    Param::new("b", ParamType::Channel),
+];
+const RGBA: [Param; 4] = [
+    Param::new("r", ParamType::Channel),
+    Param::new("g", ParamType::Channel),
+    Param::new("b", ParamType::Channel),
+    Param::new("a", ParamType::Channel),
+];
+const COLOR_RGBA: [Param; 5] = [
+    COLOR,
+    Param::new("r", ParamType::Channel),
+    Param::new("g", ParamType::Channel),
+    Param::new("b", ParamType::Channel),
+    Param::new("a", ParamType::Channel),
+];
+const HSL: [Param; 3] = [
+    Param::new("h", ParamType::Number),
+    Param::new("s", ParamType::Percent),
+    Param::new("l", ParamType::Percent),
+];
+const HSLA: [Param; 4] = [
+    Param::new("h", ParamType::Number),
+    Param::new("s", ParamType::Percent),
+    Param::new("l", ParamType::Percent),
+    Param::new("a", ParamType::Channel),
+];
+const HSV: [Param; 3] = [
+    Param::new("h", ParamType::Number),
+    Param::new("s", ParamType::Percent),
+    Param::new("v", ParamType::Percent),
+];
+const HSVA: [Param; 4] = [
+    Param::new("h", ParamType::Number),
+    Param::new("s", ParamType::Percent),
+    Param::new("v", ParamType::Percent),
+    Param::new("a", ParamType::Channel),
+];
+const LAB: [Param; 3] = [
+    Param::new("l", ParamType::Number),
+    Param::new("a", ParamType::Number),
+    Param::new("b", ParamType::Number),
+];
+const LCH: [Param; 3] = [
+    Param::new("l", ParamType::Number),
+    Param::new("c", ParamType::Number),
+    Param::new("h", ParamType::Number),
+];
+const COLOR_AMOUNT: [Param; 2] = [COLOR, Param::new("amount", ParamType::Percent)];
+const BASE_BLEND: [Param; 2] = [
+    Param::new("base", ParamType::Color),
+    Param::new("blend", ParamType::Color),
+];
+const A_B: [Param; 2] = [
+    Param::new("a", ParamType::Color),
+    Param::new("b", ParamType::Color),
+];
+
+// 組み込み関数の一覧 `defiro builtins`もこの順で表示する
+pub static BUILTINS: &[Builtin] = &[
+    fixed("rgb", &RGB, "color", eval_rgb_function),
+    fixed("rgba", &RGBA, "color", eval_rgba_function),
+    fixed("hsl", &HSL, "color", |args| eval_hsl_function(args, false)),
+    fixed("hsla", &HSLA, "color", |args| eval_hsl_function(args, true)),
+    fixed("hsv", &HSV, "color", |args| eval_hsv_function(args, false)),
+    fixed("hsva", &HSVA, "color", |args| eval_hsv_function(args, true)),
+    fixed("oklab", &LAB, "color", |args| {
+        eval_color_space_function(args, ColorSpace::Oklab)
+    }),
+    fixed("oklch", &LCH, "color", |args| {
+        eval_color_space_function(args, ColorSpace::Oklch)
+    }),
+    fixed("lab", &LAB, "color", |args| {
+        eval_color_space_function(args, ColorSpace::Lab)
+    }),
+    Builtin {
+        optional: 1,
+        ..fixed("plus", &COLOR_RGBA, "color", eval_plus_function)
+    },
+    Builtin {
+        optional: 1,
+        ..fixed("minus", &COLOR_RGBA, "color", eval_minus_function)
+    },
+    fixed("lighten", &COLOR_AMOUNT, "color", |args| {
+        eval_adjust_function(args, Adjustment::Lighten)
+    }),
+    fixed("darken", &COLOR_AMOUNT, "color", |args| {
+        eval_adjust_function(args, Adjustment::Darken)
+    }),
+    fixed("saturate", &COLOR_AMOUNT, "color", |args| {
+        eval_adjust_function(args, Adjustment::Saturate)
+    }),
+    fixed("desaturate", &COLOR_AMOUNT, "color", |args| {
+        eval_adjust_function(args, Adjustment::Desaturate)
+    }),
+    fixed(
+        "adjust_hue",
+        &[COLOR, Param::new("degrees", ParamType::Number)],
+        "color",
+        |args| eval_adjust_function(args, Adjustment::AdjustHue),
+    ),
+    fixed(
+        "mix",
+        &[
+            Param::new("a", ParamType::Color),
+            Param::new("b", ParamType::Color),
+            Param::new("weight", ParamType::Percent),
+        ],
+        "color",
+        eval_mix_function,
+    ),
+    fixed("multiply", &BASE_BLEND, "color", |args| {
+        eval_blend_function(args, BlendMode::Multiply)
+    }),
+    fixed("screen", &BASE_BLEND, "color", |args| {
+        eval_blend_function(args, BlendMode::Screen)
+    }),
+    fixed("overlay", &BASE_BLEND, "color", |args| {
+        eval_blend_function(args, BlendMode::Overlay)
+    }),
+    fixed("soft_light", &BASE_BLEND, "color", |args| {
+        eval_blend_function(args, BlendMode::SoftLight)
+    }),
+    fixed("difference", &BASE_BLEND, "color", |args| {
+        eval_blend_function(args, BlendMode::Difference)
+    }),
+    fixed(
+        "over",
+        &[
+            Param::new("fg", ParamType::Color),
+            Param::new("bg", ParamType::Color),
+        ],
+        "color",
+        eval_over_function,
+    ),
+    fixed("red", &[COLOR], "number", |args| {
+        eval_channel_function(args, Channel::Red)
+    }),
+    fixed("green", &[COLOR], "number", |args| {
+        eval_channel_function(args, Channel::Green)
+    }),
+    fixed("blue", &[COLOR], "number", |args| {
+        eval_channel_function(args, Channel::Blue)
+    }),
+    fixed("alpha", &[COLOR], "number", |args| {
+        eval_channel_function(args, Channel::Alpha)
+    }),
+    fixed("hue", &[COLOR], "number", |args| {
+        eval_channel_function(args, Channel::Hue)
+    }),
+    fixed("saturation", &[COLOR], "number", |args| {
+        eval_channel_function(args, Channel::Saturation)
+    }),
+    fixed("lightness", &[COLOR], "number", |args| {
+        eval_channel_function(args, Channel::Lightness)
+    }),
+    fixed("value", &[COLOR], "number", |args| {
+        eval_channel_function(args, Channel::Value)
+    }),
+    fixed("chroma", &[COLOR], "number", |args| {
+        eval_channel_function(args, Channel::Chroma)
+    }),
+    fixed("ok_lightness", &[COLOR], "number", |args| {
+        eval_channel_function(args, Channel::OkLightness)
+    }),
+    fixed("lab_lightness", &[COLOR], "number", |args| {
+        eval_channel_function(args, Channel::LabLightness)
+    }),
+    fixed("luminance", &[COLOR], "number", eval_luminance_function),
+    fixed("contrast", &A_B, "number", eval_contrast_function),
+    fixed(
+        "ramp",
+        &[
+            Param::new("base", ParamType::Color),
+            Param::new("n", ParamType::Integer { min: 2, max: 11 }),
+        ],
+        "palette",
+        eval_ramp_function,
+    ),
+    Builtin {
+        variadic: true,
+        ..fixed(
+            "scale",
+            &[
+                Param::new("base", ParamType::Color),
+                Param::new("step", ParamType::Integer { min: 0, max: 1000 }),
+            ],
+            "palette",
+            eval_scale_function,
+        )
+    },
+    Builtin {
+        optional: 1,
+        variadic: true,
+        ..fixed(
+            "concat",
+            &[Param::new("value", ParamType::Text)],
+            "string",
+            eval_concat_function,
+        )
+    },
+    fixed(
+        "nearest_name",
+        &[COLOR],
+        "string",
+        eval_nearest_name_function,
+    ),
+    fixed(
+        "upper",
+        &[Param::new("text", ParamType::Str)],
+        "string",
+        eval_upper_function,
+    ),
+    Builtin {
+        optional: 1,
+        variadic: true,
+        ..fixed(
+            "format",
+            &[
+                Param::new("template", ParamType::Str),
+                Param::new("value", ParamType::Text),
+            ],
+            "string",
+            eval_format_function,
+        )
+    },
+];
+
+#[cfg(test)]
+mod test {
+    use crate::{color::Color, location::Span};
+
+    use super::{find_builtin, Arg, EvalFault, Value, BUILTINS};
+
+    fn arg(value: Value) -> Arg {
+        Arg::new(value, Span::default())
+    }
+
+    #[test]
+    fn unique_names() {
+        for (i, builtin) in BUILTINS.iter().enumerate() {
+            assert!(
+                BUILTINS[..i].iter().all(|other| other.name != builtin.name),
+                "{}",
+                builtin.name
+            );
+        }
+    }
+
+    #[test]
+    fn signature() {
+        let signature = |name| find_builtin(name).unwrap().to_string();
+
+        assert_eq!(
+            signature("plus"),
+            "plus(color: color, r: channel, g: channel, b: channel, a?: channel) -> color"
+        );
+        assert_eq!(
+            signature("scale"),
+            "scale(base: color, step: integer...) -> palette"
+        );
+        assert_eq!(signature("concat"), "concat(value?: text...) -> string");
+    }
+
+    #[test]
+    fn check_args() {
+        let concat = find_builtin("concat").unwrap();
+        assert_eq!(
+            concat.call(vec![], Span::default()).map_err(|f| f.inner),
+            Ok(Value::Str(String::new()))
+        );
+
+        let scale = find_builtin("scale").unwrap();
+        let base = || arg(Value::Color(Color::new(0, 0, 0)));
+        assert_eq!(
+            scale
+                .call(vec![base()], Span::default())
+                .map_err(|f| f.inner),
+            Err(EvalFault::NumArgments { req: 2, got: 1 })
+        );
+        assert_eq!(
+            scale
+                .call(
+                    vec![base(), arg(Value::Number(0.0)), arg(Value::Number(1.5))],
+                    Span::default()
+                )
+                .map_err(|f| f.inner),
+            Err(EvalFault::ArgType {
+                param: "step",
+                req: "an integer from 0 to 1000".to_string(),
+                got: "number `1.5`".to_string()
+            })
+        );
+
+        let plus = find_builtin("plus").unwrap();
+        let args = (0..6).map(|_| arg(Value::Number(0.0))).collect();
+        assert_eq!(
+            plus.call(args, Span::default()).map_err(|f| f.inner),
+            Err(EvalFault::NumArgments { req: 5, got: 6 })
+        );
+    }
+}
@@ -1,132 +1,352 @@
-use crate::{color::Color, envroiment::Envroiment, parser::Expression};
+use crate::{
+    color::{BlendMode, Color},
+    envroiment::Envroiment,
+    location::Spanned,
+    named_color::nearest_name,
+    parser::Expression,
+};
 
+use super::builtin::Args;
 use super::{eval_expression, EvalFault, Value};
 
-pub(super) fn eval_plus_function(
-    mut args: Vec<Expression>,
+// 評価済みの引数 型が違う場合に引数の位置でエラーにするためspanを持つ
+pub(super) type Arg = Spanned<Value>;
+
+// 関数を呼ぶ前にすべての引数を左から順に評価する
+pub(super) fn eval_args(
+    args: Vec<Expression>,
     env: &mut Envroiment,
-) -> Result<Value, EvalFault> {
-    if args.len() != 4 {
-        return Err(EvalFault::NumArgments {
-            req: 4,
-            got: args.len(),
-        });
-    };
+) -> Result<Vec<Arg>, Spanned<EvalFault>> {
+    args.into_iter()
+        .map(|exp| {
+            let span = exp.span;
+            Ok(Spanned::new(eval_expression(exp, env)?, span))
+        })
+        .collect()
+}
 
-    let Expression::Int(b) = args.pop().expect("bug") else {
-        return Err(EvalFault::ArgType);
-    };
+// 引数の型と数は呼び出す前にbuiltin::Builtin::callで確認済み
 
-    let Expression::Int(g) = args.pop().expect("bug") else {
-        return Err(EvalFault::ArgType);
-    };
+// 5番目の引数を渡すとアルファ値も変化させる
+pub(super) fn eval_plus_function(args: &Args) -> Result<Value, EvalFault> {
+    let a = if args.len() > 4 { args.channel(4) } else { 0 };
+    let color = args.color(0);
 
-    let Expression::Int(r) = args.pop().expect("bug") else {
-        return Err(EvalFault::ArgType);
-    };
+    Ok(Value::Color(
+        color
+            .plus(args.channel(1), args.channel(2), args.channel(3))
+            .plus_alpha(a),
+    ))
+}
+
+pub(super) fn eval_rgb_function(args: &Args) -> Result<Value, EvalFault> {
+    Ok(Value::Color(Color::new(
+        args.channel(0),
+        args.channel(1),
+        args.channel(2),
+    )))
+}
 
-    let value = eval_expression(args.pop().expect("bug"), env)?;
+pub(super) fn eval_rgba_function(args: &Args) -> Result<Value, EvalFault> {
+    Ok(Value::Color(Color::new_rgba(
+        args.channel(0),
+        args.channel(1),
+        args.channel(2),
+        args.channel(3),
+    )))
+}
 
-    let Value::Color(color) = value else {
-        return Err(EvalFault::ArgType);
-    };
+// 5番目の引数を渡すとアルファ値も変化させる
+pub(super) fn eval_minus_function(args: &Args) -> Result<Value, EvalFault> {
+    let a = if args.len() > 4 { args.channel(4) } else { 0 };
+    let color = args.color(0);
 
-    Ok(Value::Color(color.plus(r, g, b)))
+    Ok(Value::Color(
+        color
+            .minus(args.channel(1), args.channel(2), args.channel(3))
+            .minus_alpha(a),
+    ))
 }
 
-pub(super) fn eval_rgb_function(args: Vec<Expression>) -> Result<Value, EvalFault> {
-    if args.len() != 3 {
-        return Err(EvalFault::NumArgments {
-            req: 3,
-            got: args.len(),
-        });
-    };
-    let Expression::Int(r) = args[0] else {
-        return Err(EvalFault::ArgType);
-    };
-    let Expression::Int(g) = args[1] else {
-        return Err(EvalFault::ArgType);
-    };
-    let Expression::Int(b) = args[2] else {
-        return Err(EvalFault::ArgType);
+// hsl(h, s, l) hsla(h, s, l, a)
+// hは角度、sとlは0から100のパーセント、aは0から255
+pub(super) fn eval_hsl_function(args: &Args, alpha: bool) -> Result<Value, EvalFault> {
+    let color = Color::from_hsl(args.number(0), args.number(1), args.number(2));
+
+    if alpha {
+        Ok(Value::Color(color.with_alpha(args.channel(3))))
+    } else {
+        Ok(Value::Color(color))
+    }
+}
+
+// hsv(h, s, v) hsva(h, s, v, a)
+pub(super) fn eval_hsv_function(args: &Args, alpha: bool) -> Result<Value, EvalFault> {
+    let color = Color::from_hsv(args.number(0), args.number(1), args.number(2));
+
+    if alpha {
+        Ok(Value::Color(color.with_alpha(args.channel(3))))
+    } else {
+        Ok(Value::Color(color))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Channel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    Hue,
+    Saturation,
+    Lightness,
+    Value,
+    Chroma,
+    OkLightness,
+    LabLightness,
+}
+
+// red(c) hue(c) などのチャンネルを数値として取り出す関数
+// saturation lightness valueは0から100のパーセントで返す
+// valueはHSVの明度
+// chromaとok_lightnessはOKLCH、lab_lightnessはCIELABの明度(0から100)
+pub(super) fn eval_channel_function(args: &Args, channel: Channel) -> Result<Value, EvalFault> {
+    let color = args.color(0);
+    let (h, s, l) = color.to_hsl();
+
+    let number = match channel {
+        Channel::Red => color.red() as f64,
+        Channel::Green => color.green() as f64,
+        Channel::Blue => color.blue() as f64,
+        Channel::Alpha => color.alpha() as f64,
+        Channel::Hue => h,
+        Channel::Saturation => s * 100.0,
+        Channel::Lightness => l * 100.0,
+        Channel::Value => color.to_hsv().2 * 100.0,
+        Channel::Chroma => color.to_oklch().1,
+        Channel::OkLightness => color.to_oklch().0 * 100.0,
+        Channel::LabLightness => color.to_lab().0,
     };
 
-    Ok(Value::Color(Color::new(r, g, b)))
+    Ok(Value::Number(number))
 }
 
-pub(super) fn eval_minus_function(
-    mut args: Vec<Expression>,
-    env: &mut Envroiment,
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Adjustment {
+    Lighten,
+    Darken,
+    Saturate,
+    Desaturate,
+    AdjustHue,
+}
+
+// lighten(c, pct) などHSL空間で色を調整する関数
+// adjust_hueだけは2番目の引数が角度
+pub(super) fn eval_adjust_function(
+    args: &Args,
+    adjustment: Adjustment,
 ) -> Result<Value, EvalFault> {
-    if args.len() != 4 {
-        return Err(EvalFault::NumArgments {
-            req: 4,
-            got: args.len(),
-        });
-    };
+    let color = args.color(0);
+    let amount = args.number(1);
 
-    let Expression::Int(b) = args.pop().expect("bug") else {
-        return Err(EvalFault::ArgType);
+    let adjusted = match adjustment {
+        Adjustment::Lighten => color.lighten(amount),
+        Adjustment::Darken => color.darken(amount),
+        Adjustment::Saturate => color.saturate(amount),
+        Adjustment::Desaturate => color.desaturate(amount),
+        Adjustment::AdjustHue => color.adjust_hue(amount),
     };
 
-    let Expression::Int(g) = args.pop().expect("bug") else {
-        return Err(EvalFault::ArgType);
-    };
+    Ok(Value::Color(adjusted))
+}
 
-    let Expression::Int(r) = args.pop().expect("bug") else {
-        return Err(EvalFault::ArgType);
-    };
+// mix(a, b, weight) weightはaの割合を0から100のパーセントで指定する
+pub(super) fn eval_mix_function(args: &Args) -> Result<Value, EvalFault> {
+    Ok(Value::Color(
+        args.color(0).mix(args.color(1), args.number(2)),
+    ))
+}
+
+// multiply(base, blend) baseが下の色
+pub(super) fn eval_blend_function(args: &Args, mode: BlendMode) -> Result<Value, EvalFault> {
+    Ok(Value::Color(args.color(0).blend(args.color(1), mode)))
+}
+
+// over(fg, bg)
+pub(super) fn eval_over_function(args: &Args) -> Result<Value, EvalFault> {
+    Ok(Value::Color(args.color(0).over(args.color(1))))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum ColorSpace {
+    Oklab,
+    Oklch,
+    Lab,
+}
 
-    let value = eval_expression(args.pop().expect("bug"), env)?;
+// oklab(l, a, b) oklch(l, c, h) lab(l, a, b)
+// sRGBの範囲外の色は彩度を落としてから変換される
+pub(super) fn eval_color_space_function(
+    args: &Args,
+    space: ColorSpace,
+) -> Result<Value, EvalFault> {
+    let (x, y, z) = (args.number(0), args.number(1), args.number(2));
 
-    let Value::Color(color) = value else {
-        return Err(EvalFault::ArgType);
+    let color = match space {
+        ColorSpace::Oklab => Color::from_oklab(x, y, z),
+        ColorSpace::Oklch => Color::from_oklch((x, y, z)),
+        ColorSpace::Lab => Color::from_lab(x, y, z),
     };
 
-    Ok(Value::Color(color.minus(r, g, b)))
+    Ok(Value::Color(color))
+}
+
+// luminance(c) WCAGの相対輝度を0から1で返す
+pub(super) fn eval_luminance_function(args: &Args) -> Result<Value, EvalFault> {
+    Ok(Value::Number(args.color(0).luminance()))
+}
+
+// contrast(a, b) WCAGのコントラスト比を1から21で返す
+pub(super) fn eval_contrast_function(args: &Args) -> Result<Value, EvalFault> {
+    Ok(Value::Number(args.color(0).contrast(args.color(1))))
+}
+
+// ramp(base, n) n段階のパレットを作る
+// 段階の名前は50, 100, 200, ... (n-1)*100 となり、n>=6なら500がbaseになる
+pub(super) fn eval_ramp_function(args: &Args) -> Result<Value, EvalFault> {
+    Ok(Value::Palette(ramp(args.color(0), args.number(1) as u32)))
+}
+
+pub(super) fn ramp(base: &Color, n: u32) -> Vec<(u32, Color)> {
+    (0..n)
+        .map(|i| if i == 0 { 50 } else { i * 100 })
+        .map(|step| (step, base.ramp_step(step as f64)))
+        .collect()
+}
+
+// scale(base, step1, step2, ...) 段階を0から1000で指定してパレットを作る
+pub(super) fn eval_scale_function(args: &Args) -> Result<Value, EvalFault> {
+    let base = args.color(0);
+    let palette = (1..args.len())
+        .map(|i| args.number(i))
+        .map(|step| (step as u32, base.ramp_step(step)))
+        .collect();
+
+    Ok(Value::Palette(palette))
+}
+
+// nearest_name(c) 一番近いCSSの色の名前 `/// nearest_name(c)`のような注釈に使う
+pub(super) fn eval_nearest_name_function(args: &Args) -> Result<Value, EvalFault> {
+    Ok(Value::Str(nearest_name(args.color(0)).to_string()))
+}
+
+// concat(a, b, ...)
+pub(super) fn eval_concat_function(args: &Args) -> Result<Value, EvalFault> {
+    let text = (0..args.len()).map(|i| args.string(i)).collect();
+    Ok(Value::Str(text))
+}
+
+pub(super) fn eval_upper_function(args: &Args) -> Result<Value, EvalFault> {
+    Ok(Value::Str(args.string(0).to_uppercase()))
+}
+
+// format("{} on {}", fg, bg) `{}`を前から順に引数で置き換える
+// `{{`と`}}`は`{`と`}`になる
+pub(super) fn eval_format_function(args: &Args) -> Result<Value, EvalFault> {
+    let template = args.string(0);
+
+    let mut text = String::new();
+    let mut values = (1..args.len()).map(|i| args.string(i));
+    let mut placeholders = 0;
+    let mut chars = template.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match (ch, chars.peek()) {
+            ('{', Some('}')) => {
+                chars.next();
+                placeholders += 1;
+                if let Some(value) = values.next() {
+                    text += value;
+                }
+            }
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                text.push(ch);
+            }
+            (ch, _) => text.push(ch),
+        }
+    }
+
+    if placeholders != args.len() - 1 {
+        return Err(EvalFault::NumArgments {
+            req: placeholders + 1,
+            got: args.len(),
+        });
+    }
+
+    Ok(Value::Str(text))
 }
 
 #[cfg(test)]
 mod test {
     use std::vec;
 
-    use crate::{
-        color::Color,
-        envroiment::Envroiment,
-        eval::function::{eval_minus_function, eval_plus_function},
-        parser::Expression,
-    };
+    use crate::{color::Color, envroiment::Envroiment, location::Span, parser::Expression};
+
+    use super::{eval_args, Arg, EvalFault, Value};
+    use crate::eval::builtin::find_builtin;
+
+    // 組み込み関数の一覧から名前で探して呼ぶ
+    fn call(name: &str, args: Vec<Arg>) -> Result<Value, EvalFault> {
+        find_builtin(name)
+            .unwrap()
+            .call(args, Span::default())
+            .map_err(|fault| fault.inner)
+    }
+
+    fn evaluated(exps: Vec<Expression>, env: &mut Envroiment) -> Vec<Arg> {
+        eval_args(exps, env).unwrap()
+    }
 
-    use super::{EvalFault, Value};
+    fn literals(exps: Vec<Expression>) -> Vec<Arg> {
+        evaluated(exps, &mut Envroiment::new())
+    }
+
+    fn arg_type(param: &'static str, req: &str, got: &str) -> EvalFault {
+        EvalFault::ArgType {
+            param,
+            req: req.to_string(),
+            got: got.to_string(),
+        }
+    }
 
     #[test]
     fn test_eval_minus_func() {
         assert_eval_minus_func_ok(
             vec![
-                Expression::Color(Color::new(110, 110, 110)),
-                Expression::Int(10),
-                Expression::Int(10),
-                Expression::Int(10),
+                Expression::color(Color::new(110, 110, 110)),
+                Expression::number(10.0),
+                Expression::number(10.0),
+                Expression::number(10.0),
             ],
             vec![],
             Value::Color(Color::new(100, 100, 100)),
         );
         assert_eval_minus_func_ok(
             vec![
-                Expression::Color(Color::new(0, 110, 254)),
-                Expression::Int(1),
-                Expression::Int(111),
-                Expression::Int(255),
+                Expression::color(Color::new(0, 110, 254)),
+                Expression::number(1.0),
+                Expression::number(111.0),
+                Expression::number(255.0),
             ],
             vec![],
             Value::Color(Color::new(0, 0, 0)),
         );
         assert_eval_minus_func_ok(
             vec![
-                Expression::Identifier("hello".to_string()),
-                Expression::Int(100),
-                Expression::Int(10),
-                Expression::Int(0),
+                Expression::identifier("hello".to_string()),
+                Expression::number(100.0),
+                Expression::number(10.0),
+                Expression::number(0.0),
             ],
             vec![("hello", Color::new(100, 200, 100))],
             Value::Color(Color::new(0, 190, 100)),
@@ -137,10 +357,10 @@ mod test {
     fn test_eval_plus_func() {
         assert_eval_plus_func_ok(
             vec![
-                Expression::Color(Color::new(10, 10, 10)),
-                Expression::Int(10),
-                Expression::Int(10),
-                Expression::Int(10),
+                Expression::color(Color::new(10, 10, 10)),
+                Expression::number(10.0),
+                Expression::number(10.0),
+                Expression::number(10.0),
             ],
             vec![],
             Value::Color(Color::new(20, 20, 20)),
@@ -148,10 +368,10 @@ mod test {
 
         assert_eval_plus_func_ok(
             vec![
-                Expression::Identifier("hello".to_string()),
-                Expression::Int(100),
-                Expression::Int(10),
-                Expression::Int(0),
+                Expression::identifier("hello".to_string()),
+                Expression::number(100.0),
+                Expression::number(10.0),
+                Expression::number(0.0),
             ],
             vec![("hello", Color::new(100, 200, 255))],
             Value::Color(Color::new(200, 210, 255)),
@@ -159,58 +379,554 @@ mod test {
 
         assert_eval_plus_func_ok(
             vec![
-                Expression::Color(Color::new(255, 100, 10)),
-                Expression::Int(100),
-                Expression::Int(10),
-                Expression::Int(0),
+                Expression::color(Color::new(255, 100, 10)),
+                Expression::number(100.0),
+                Expression::number(10.0),
+                Expression::number(0.0),
             ],
             vec![],
             Value::Color(Color::new(255, 110, 10)),
         );
     }
 
+    #[test]
+    fn test_eval_alpha() {
+        assert_eval_plus_func_ok(
+            vec![
+                Expression::color(Color::new_rgba(10, 10, 10, 100)),
+                Expression::number(10.0),
+                Expression::number(10.0),
+                Expression::number(10.0),
+            ],
+            vec![],
+            Value::Color(Color::new_rgba(20, 20, 20, 100)),
+        );
+        assert_eval_plus_func_ok(
+            vec![
+                Expression::color(Color::new_rgba(10, 10, 10, 100)),
+                Expression::number(0.0),
+                Expression::number(0.0),
+                Expression::number(0.0),
+                Expression::number(200.0),
+            ],
+            vec![],
+            Value::Color(Color::new(10, 10, 10)),
+        );
+        assert_eval_minus_func_ok(
+            vec![
+                Expression::color(Color::new(10, 10, 10)),
+                Expression::number(0.0),
+                Expression::number(0.0),
+                Expression::number(0.0),
+                Expression::number(55.0),
+            ],
+            vec![],
+            Value::Color(Color::new_rgba(10, 10, 10, 200)),
+        );
+
+        assert_eq!(
+            call(
+                "rgba",
+                literals(vec![
+                    Expression::number(1.0),
+                    Expression::number(2.0),
+                    Expression::number(3.0),
+                    Expression::number(4.0),
+                ])
+            ),
+            Ok(Value::Color(Color::new_rgba(1, 2, 3, 4)))
+        );
+        assert_eq!(
+            call(
+                "rgba",
+                literals(vec![
+                    Expression::number(1.0),
+                    Expression::number(2.0),
+                    Expression::number(3.0),
+                ])
+            ),
+            Err(EvalFault::NumArgments { req: 4, got: 3 })
+        );
+        assert_eq!(
+            call(
+                "rgba",
+                literals(vec![
+                    Expression::number(1.0),
+                    Expression::number(2.0),
+                    Expression::number(3.0),
+                    Expression::color(Color::new(1, 2, 3)),
+                ])
+            ),
+            Err(arg_type("a", "an integer from 0 to 255", "color `#010203`"))
+        );
+    }
+
+    #[test]
+    fn test_eval_rgb_percent() {
+        assert_eq!(
+            call(
+                "rgba",
+                literals(vec![
+                    Expression::percent(100.0),
+                    Expression::percent(50.0),
+                    Expression::number(0.0),
+                    Expression::percent(50.0),
+                ])
+            ),
+            Ok(Value::Color(Color::new_rgba(255, 128, 0, 128)))
+        );
+        assert_eq!(
+            call(
+                "rgb",
+                literals(vec![
+                    Expression::percent(101.0),
+                    Expression::number(0.0),
+                    Expression::number(0.0),
+                ])
+            ),
+            Err(arg_type(
+                "r",
+                "an integer from 0 to 255",
+                "percentage `101%`"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_eval_rgb_func_err() {
+        assert_eq!(
+            call(
+                "rgb",
+                literals(vec![
+                    Expression::number(256.0),
+                    Expression::number(0.0),
+                    Expression::number(0.0),
+                ])
+            ),
+            Err(arg_type("r", "an integer from 0 to 255", "number `256`"))
+        );
+        assert_eq!(
+            call(
+                "rgb",
+                literals(vec![
+                    Expression::number(1.5),
+                    Expression::number(0.0),
+                    Expression::number(0.0),
+                ])
+            ),
+            Ok(Value::Color(Color::new(2, 0, 0)))
+        );
+    }
+
+    #[test]
+    fn test_eval_hsl_hsv_func() {
+        let numbers = |nums: Vec<f64>| nums.into_iter().map(Expression::number).collect();
+
+        assert_eq!(
+            call("hsl", literals(numbers(vec![210.0, 50.0, 40.0]))),
+            Ok(Value::Color(Color::new(51, 102, 153)))
+        );
+        assert_eq!(
+            call("hsl", literals(numbers(vec![360.0, 100.0, 50.0]))),
+            Ok(Value::Color(Color::new(255, 0, 0)))
+        );
+        assert_eq!(
+            call("hsla", literals(numbers(vec![0.0, 100.0, 50.0, 128.0]))),
+            Ok(Value::Color(Color::new_rgba(255, 0, 0, 128)))
+        );
+        assert_eq!(
+            call("hsv", literals(numbers(vec![60.0, 100.0, 100.0]))),
+            Ok(Value::Color(Color::new(255, 255, 0)))
+        );
+        assert_eq!(
+            call("hsva", literals(numbers(vec![60.0, 100.0, 100.0, 0.0]))),
+            Ok(Value::Color(Color::new_rgba(255, 255, 0, 0)))
+        );
+
+        assert_eq!(
+            call("hsl", literals(numbers(vec![0.0, 100.0, 50.0, 128.0]))),
+            Err(EvalFault::NumArgments { req: 3, got: 4 })
+        );
+        assert_eq!(
+            call("hsl", literals(numbers(vec![0.0, 101.0, 50.0]))),
+            Err(arg_type("s", "a percentage from 0 to 100", "number `101`"))
+        );
+        assert_eq!(
+            call("hsva", literals(numbers(vec![0.0, 100.0, 50.0]))),
+            Err(EvalFault::NumArgments { req: 4, got: 3 })
+        );
+    }
+
+    #[test]
+    fn test_eval_channel_func() {
+        let mut env = Envroiment::new();
+        env.set(
+            "hello".to_string(),
+            Value::Color(Color::new_rgba(51, 102, 153, 10)),
+        );
+
+        let mut channel = |name: &str| {
+            let args = vec![Expression::identifier("hello".to_string())];
+            let Ok(Value::Number(number)) = call(name, evaluated(args, &mut env)) else {
+                panic!("{}", name);
+            };
+            number.round()
+        };
+
+        assert_eq!(channel("red"), 51.0);
+        assert_eq!(channel("green"), 102.0);
+        assert_eq!(channel("blue"), 153.0);
+        assert_eq!(channel("alpha"), 10.0);
+        assert_eq!(channel("hue"), 210.0);
+        assert_eq!(channel("saturation"), 50.0);
+        assert_eq!(channel("lightness"), 40.0);
+        assert_eq!(channel("value"), 60.0);
+        assert_eq!(channel("ok_lightness"), 50.0);
+        assert_eq!(channel("lab_lightness"), 42.0);
+
+        assert_eq!(
+            call("hue", evaluated(vec![Expression::number(1.0)], &mut env)),
+            Err(arg_type("color", "a color", "number `1`"))
+        );
+        assert_eq!(
+            call("hue", evaluated(vec![], &mut env)),
+            Err(EvalFault::NumArgments { req: 1, got: 0 })
+        );
+    }
+
+    #[test]
+    fn test_eval_adjust_func() {
+        assert_eval_adjust_func(
+            "lighten",
+            vec![
+                Expression::color(Color::new(51, 102, 153)),
+                Expression::number(20.0),
+            ],
+            vec![],
+            Ok(Value::Color(Color::new(102, 153, 204))),
+        );
+        assert_eval_adjust_func(
+            "lighten",
+            vec![
+                Expression::color(Color::new(51, 102, 153)),
+                Expression::percent(20.0),
+            ],
+            vec![],
+            Ok(Value::Color(Color::new(102, 153, 204))),
+        );
+        assert_eval_adjust_func(
+            "darken",
+            vec![
+                Expression::identifier("hello".to_string()),
+                Expression::number(20.0),
+            ],
+            vec![("hello", Color::new(51, 102, 153))],
+            Ok(Value::Color(Color::new(26, 51, 77))),
+        );
+        assert_eval_adjust_func(
+            "saturate",
+            vec![
+                Expression::color(Color::new(51, 102, 153)),
+                Expression::number(50.0),
+            ],
+            vec![],
+            Ok(Value::Color(Color::new(0, 102, 204))),
+        );
+        assert_eval_adjust_func(
+            "desaturate",
+            vec![
+                Expression::color(Color::new_rgba(51, 102, 153, 10)),
+                Expression::number(100.0),
+            ],
+            vec![],
+            Ok(Value::Color(Color::new_rgba(102, 102, 102, 10))),
+        );
+        assert_eval_adjust_func(
+            "adjust_hue",
+            vec![
+                Expression::color(Color::new(51, 102, 153)),
+                Expression::number(540.0),
+            ],
+            vec![],
+            Ok(Value::Color(Color::new(153, 102, 51))),
+        );
+
+        assert_eval_adjust_func(
+            "lighten",
+            vec![Expression::color(Color::new(51, 102, 153))],
+            vec![],
+            Err(EvalFault::NumArgments { req: 2, got: 1 }),
+        );
+        assert_eval_adjust_func(
+            "lighten",
+            vec![
+                Expression::color(Color::new(51, 102, 153)),
+                Expression::number(101.0),
+            ],
+            vec![],
+            Err(arg_type(
+                "amount",
+                "a percentage from 0 to 100",
+                "number `101`",
+            )),
+        );
+        assert_eval_adjust_func(
+            "darken",
+            vec![Expression::number(10.0), Expression::number(10.0)],
+            vec![],
+            Err(arg_type("color", "a color", "number `10`")),
+        );
+    }
+
+    fn assert_eval_adjust_func(
+        name: &str,
+        args: Vec<Expression>,
+        env_vars: Vec<(&str, Color)>,
+        assert_val: Result<Value, EvalFault>,
+    ) {
+        let mut env = Envroiment::new();
+        for var in env_vars {
+            env.set(var.0.to_string(), Value::Color(var.1))
+        }
+
+        let result = call(name, evaluated(args, &mut env));
+
+        assert_eq!(result, assert_val);
+    }
+
+    #[test]
+    fn test_eval_mix_blend_over_func() {
+        let mut env = Envroiment::new();
+        env.set("bg".to_string(), Value::Color(Color::new(255, 255, 255)));
+        env.set(
+            "accent".to_string(),
+            Value::Color(Color::new_rgba(0, 0, 0, 128)),
+        );
+        let bg = || Expression::identifier("bg".to_string());
+        let accent = || Expression::identifier("accent".to_string());
+
+        assert_eq!(
+            call(
+                "mix",
+                evaluated(vec![accent(), bg(), Expression::number(50.0)], &mut env)
+            ),
+            Ok(Value::Color(Color::new_rgba(128, 128, 128, 192)))
+        );
+        assert_eq!(
+            call(
+                "mix",
+                evaluated(vec![accent(), bg(), Expression::number(100.0)], &mut env)
+            ),
+            Ok(Value::Color(Color::new_rgba(0, 0, 0, 128)))
+        );
+        assert_eq!(
+            call("mix", evaluated(vec![accent(), bg()], &mut env)),
+            Err(EvalFault::NumArgments { req: 3, got: 2 })
+        );
+        assert_eq!(
+            call("mix", evaluated(vec![accent(), bg(), bg()], &mut env)),
+            Err(arg_type(
+                "weight",
+                "a percentage from 0 to 100",
+                "color `#ffffff`"
+            ))
+        );
+
+        assert_eq!(
+            call(
+                "multiply",
+                evaluated(
+                    vec![Expression::color(Color::new(200, 100, 50)), bg()],
+                    &mut env
+                )
+            ),
+            Ok(Value::Color(Color::new(200, 100, 50)))
+        );
+        assert_eq!(
+            call(
+                "screen",
+                evaluated(vec![bg(), Expression::number(1.0)], &mut env)
+            ),
+            Err(arg_type("blend", "a color", "number `1`"))
+        );
+
+        assert_eq!(
+            call("over", evaluated(vec![accent(), bg()], &mut env)),
+            Ok(Value::Color(Color::new(127, 127, 127)))
+        );
+        assert_eq!(
+            call("over", evaluated(vec![accent()], &mut env)),
+            Err(EvalFault::NumArgments { req: 2, got: 1 })
+        );
+    }
+
+    #[test]
+    fn test_eval_color_space_func() {
+        let numbers = |nums: Vec<f64>| nums.into_iter().map(Expression::number).collect();
+
+        assert_eq!(
+            call("oklab", literals(numbers(vec![0.628, 0.2249, 0.1258]))),
+            Ok(Value::Color(Color::new(255, 0, 0)))
+        );
+        assert_eq!(
+            call("oklch", literals(numbers(vec![0.628, 0.2577, 29.23]))),
+            Ok(Value::Color(Color::new(255, 0, 0)))
+        );
+        assert_eq!(
+            call("lab", literals(numbers(vec![54.29, 80.8, 69.89]))),
+            Ok(Value::Color(Color::new(255, 0, 0)))
+        );
+        assert_eq!(
+            call("lab", literals(numbers(vec![1.0, 0.0]))),
+            Err(EvalFault::NumArgments { req: 3, got: 2 })
+        );
+        assert_eq!(
+            call(
+                "oklch",
+                literals(vec![
+                    Expression::number(1.0),
+                    Expression::number(1.0),
+                    Expression::color(Color::new(0, 0, 0))
+                ])
+            ),
+            Err(arg_type("h", "a number", "color `#000000`"))
+        );
+    }
+
+    #[test]
+    fn test_eval_contrast_func() {
+        let mut env = Envroiment::new();
+        env.set("fg".to_string(), Value::Color(Color::new(0, 0, 0)));
+        let fg = || Expression::identifier("fg".to_string());
+        let white = || Expression::color(Color::new(255, 255, 255));
+
+        assert_eq!(
+            call("contrast", evaluated(vec![fg(), white()], &mut env)),
+            Ok(Value::Number(21.0))
+        );
+        assert_eq!(
+            call("contrast", evaluated(vec![fg()], &mut env)),
+            Err(EvalFault::NumArgments { req: 2, got: 1 })
+        );
+        assert_eq!(
+            call("luminance", evaluated(vec![white()], &mut env)),
+            Ok(Value::Number(1.0))
+        );
+        assert_eq!(
+            call(
+                "luminance",
+                evaluated(vec![Expression::number(1.0)], &mut env)
+            ),
+            Err(arg_type("color", "a color", "number `1`"))
+        );
+    }
+
+    #[test]
+    fn test_eval_ramp_scale_func() {
+        let mut env = Envroiment::new();
+        let base = Color::new(51, 102, 204);
+        env.set("base".to_string(), Value::Color(base.clone()));
+        let base_exp = || Expression::identifier("base".to_string());
+
+        let Ok(Value::Palette(palette)) = call(
+            "ramp",
+            evaluated(vec![base_exp(), Expression::number(10.0)], &mut env),
+        ) else {
+            panic!();
+        };
+        let steps: Vec<u32> = palette.iter().map(|(step, _)| *step).collect();
+        assert_eq!(steps, vec![50, 100, 200, 300, 400, 500, 600, 700, 800, 900]);
+        assert_eq!(palette[5].1, base);
+
+        let Ok(Value::Palette(palette)) = call(
+            "scale",
+            evaluated(
+                vec![
+                    base_exp(),
+                    Expression::number(100.0),
+                    Expression::number(500.0),
+                    Expression::number(950.0),
+                ],
+                &mut env,
+            ),
+        ) else {
+            panic!();
+        };
+        let steps: Vec<u32> = palette.iter().map(|(step, _)| *step).collect();
+        assert_eq!(steps, vec![100, 500, 950]);
+        assert_eq!(palette[1].1, base);
+
+        assert_eq!(
+            call(
+                "ramp",
+                evaluated(vec![base_exp(), Expression::number(1.0)], &mut env)
+            ),
+            Err(arg_type("n", "an integer from 2 to 11", "number `1`"))
+        );
+        assert_eq!(
+            call("ramp", evaluated(vec![base_exp()], &mut env)),
+            Err(EvalFault::NumArgments { req: 2, got: 1 })
+        );
+        assert_eq!(
+            call("scale", evaluated(vec![base_exp()], &mut env)),
+            Err(EvalFault::NumArgments { req: 2, got: 1 })
+        );
+        assert_eq!(
+            call(
+                "scale",
+                evaluated(vec![base_exp(), Expression::number(1001.0)], &mut env)
+            ),
+            Err(arg_type(
+                "step",
+                "an integer from 0 to 1000",
+                "number `1001`"
+            ))
+        );
+    }
+
     #[test]
     fn test_eval_plus_func_err() {
         assert_eval_plus_func_err(vec![], vec![], EvalFault::NumArgments { req: 4, got: 0 });
 
         assert_eval_plus_func_err(
             vec![
-                Expression::Int(1),
-                Expression::Int(1),
-                Expression::Int(1),
-                Expression::Int(1),
-                Expression::Int(1),
+                Expression::number(1.0),
+                Expression::number(1.0),
+                Expression::number(1.0),
+                Expression::number(1.0),
+                Expression::number(1.0),
+                Expression::number(1.0),
             ],
             vec![],
-            EvalFault::NumArgments { req: 4, got: 5 },
+            EvalFault::NumArgments { req: 5, got: 6 },
         );
 
         assert_eval_plus_func_err(
-            vec![Expression::Int(1)],
+            vec![Expression::number(1.0)],
             vec![],
             EvalFault::NumArgments { req: 4, got: 1 },
         );
 
         assert_eval_plus_func_err(
             vec![
-                Expression::Int(0),
-                Expression::Int(100),
-                Expression::Int(10),
-                Expression::Int(0),
+                Expression::number(0.0),
+                Expression::number(100.0),
+                Expression::number(10.0),
+                Expression::number(0.0),
             ],
             vec![],
-            EvalFault::ArgType,
+            arg_type("color", "a color", "number `0`"),
         );
 
         assert_eval_plus_func_err(
             vec![
-                Expression::Int(0),
-                Expression::Color(Color::new(10, 10, 10)),
-                Expression::Int(10),
-                Expression::Int(0),
+                Expression::number(0.0),
+                Expression::color(Color::new(10, 10, 10)),
+                Expression::number(10.0),
+                Expression::number(0.0),
             ],
             vec![],
-            EvalFault::ArgType,
+            arg_type("color", "a color", "number `0`"),
         );
     }
 
@@ -220,42 +936,43 @@ mod test {
 
         assert_eval_minus_func_err(
             vec![
-                Expression::Int(1),
-                Expression::Int(1),
-                Expression::Int(1),
-                Expression::Int(1),
-                Expression::Int(1),
+                Expression::number(1.0),
+                Expression::number(1.0),
+                Expression::number(1.0),
+                Expression::number(1.0),
+                Expression::number(1.0),
+                Expression::number(1.0),
             ],
             vec![],
-            EvalFault::NumArgments { req: 4, got: 5 },
+            EvalFault::NumArgments { req: 5, got: 6 },
         );
 
         assert_eval_minus_func_err(
-            vec![Expression::Int(1)],
+            vec![Expression::number(1.0)],
             vec![],
             EvalFault::NumArgments { req: 4, got: 1 },
         );
 
         assert_eval_minus_func_err(
             vec![
-                Expression::Int(0),
-                Expression::Int(100),
-                Expression::Int(10),
-                Expression::Int(0),
+                Expression::number(0.0),
+                Expression::number(100.0),
+                Expression::number(10.0),
+                Expression::number(0.0),
             ],
             vec![],
-            EvalFault::ArgType,
+            arg_type("color", "a color", "number `0`"),
         );
 
         assert_eval_minus_func_err(
             vec![
-                Expression::Int(0),
-                Expression::Color(Color::new(10, 10, 10)),
-                Expression::Int(10),
-                Expression::Int(0),
+                Expression::number(0.0),
+                Expression::color(Color::new(10, 10, 10)),
+                Expression::number(10.0),
+                Expression::number(0.0),
             ],
             vec![],
-            EvalFault::ArgType,
+            arg_type("color", "a color", "number `0`"),
         );
     }
     fn assert_eval_minus_func_err(
@@ -265,10 +982,10 @@ mod test {
     ) {
         let mut env = Envroiment::new();
         for var in env_vars {
-            env.set(var.0.to_string(), var.1)
+            env.set(var.0.to_string(), Value::Color(var.1))
         }
 
-        let result = eval_minus_function(args, &mut env).unwrap_err();
+        let result = call("minus", evaluated(args, &mut env)).unwrap_err();
 
         assert_eq!(result, assert_val);
     }
@@ -280,10 +997,10 @@ mod test {
     ) {
         let mut env = Envroiment::new();
         for var in env_vars {
-            env.set(var.0.to_string(), var.1)
+            env.set(var.0.to_string(), Value::Color(var.1))
         }
 
-        let result = eval_minus_function(args, &mut env).unwrap();
+        let result = call("minus", evaluated(args, &mut env)).unwrap();
 
         assert_eq!(result, assert_val);
     }
@@ -294,10 +1011,10 @@ mod test {
     ) {
         let mut env = Envroiment::new();
         for var in env_vars {
-            env.set(var.0.to_string(), var.1)
+            env.set(var.0.to_string(), Value::Color(var.1))
         }
 
-        let result = eval_plus_function(args, &mut env).unwrap_err();
+        let result = call("plus", evaluated(args, &mut env)).unwrap_err();
 
         assert_eq!(result, assert_val);
     }
@@ -309,11 +1026,60 @@ mod test {
     ) {
         let mut env = Envroiment::new();
         for var in env_vars {
-            env.set(var.0.to_string(), var.1)
+            env.set(var.0.to_string(), Value::Color(var.1))
         }
 
-        let result = eval_plus_function(args, &mut env).unwrap();
+        let result = call("plus", evaluated(args, &mut env)).unwrap();
 
         assert_eq!(result, assert_val);
     }
+
+    #[test]
+    fn test_eval_string_func() {
+        assert_eq!(
+            call(
+                "concat",
+                literals(vec![
+                    Expression::string("gray "),
+                    Expression::number(50.0),
+                    Expression::string(" "),
+                    Expression::color(Color::new(0x80, 0x80, 0x80)),
+                ])
+            ),
+            Ok(Value::Str("gray 50 #808080".to_string()))
+        );
+        assert_eq!(
+            call("upper", literals(vec![Expression::string("Dark ä")])),
+            Ok(Value::Str("DARK Ä".to_string()))
+        );
+        assert_eq!(
+            call("upper", literals(vec![Expression::number(1.0)])),
+            Err(arg_type("text", "a string", "number `1`"))
+        );
+        assert_eq!(
+            call(
+                "format",
+                literals(vec![
+                    Expression::string("{} on {} {{}}"),
+                    Expression::color(Color::new(0, 0, 0)),
+                    Expression::percent(50.0),
+                ])
+            ),
+            Ok(Value::Str("#000000 on 50% {}".to_string()))
+        );
+        assert_eq!(
+            call(
+                "format",
+                literals(vec![Expression::string("{} {}"), Expression::number(1.0)])
+            ),
+            Err(EvalFault::NumArgments { req: 3, got: 2 })
+        );
+        assert_eq!(
+            call(
+                "nearest_name",
+                literals(vec![Expression::color(Color::new(0x64, 0x95, 0xee))])
+            ),
+            Ok(Value::Str("cornflowerblue".to_string()))
+        );
+    }
 }
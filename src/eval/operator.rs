@@ -0,0 +1,201 @@
+use crate::parser::BinaryOp;
+
+use super::{EvalFault, Value};
+
+fn apply(op: BinaryOp, left: f64, right: f64) -> f64 {
+    match op {
+        BinaryOp::Add => left + right,
+        BinaryOp::Sub => left - right,
+        BinaryOp::Mul => left * right,
+        BinaryOp::Div => left / right,
+    }
+}
+
+// 色はRGBのチャンネルごとに計算し、Color::plusと同じく0から255に丸める
+// 色 + 色、色 - 色、色 (+ - * /) 数、数 (+ *) 色 を受け付ける
+// パーセントは パーセント (+ -) パーセント、パーセント (* /) 数、数 * パーセント がパーセントになり
+// 色 (* /) パーセント、パーセント * 色 は割合として色にかける
+pub(super) fn eval_binary_operation(
+    op: BinaryOp,
+    left: Value,
+    right: Value,
+) -> Result<Value, EvalFault> {
+    let divide_by_zero =
+        op == BinaryOp::Div && matches!(right, Value::Number(n) | Value::Percent(n) if n == 0.0);
+    if divide_by_zero {
+        return Err(EvalFault::DivisionByZero);
+    }
+
+    let value = match (left, right) {
+        (Value::Number(l), Value::Number(r)) => Ok(Value::Number(apply(op, l, r))),
+        (Value::Color(c), Value::Number(n)) => {
+            Ok(Value::Color(c.map_channels(|ch| apply(op, ch, n))))
+        }
+        (Value::Number(n), Value::Color(c)) if matches!(op, BinaryOp::Add | BinaryOp::Mul) => {
+            Ok(Value::Color(c.map_channels(|ch| apply(op, n, ch))))
+        }
+        (Value::Color(l), Value::Color(r)) if matches!(op, BinaryOp::Add | BinaryOp::Sub) => {
+            Ok(Value::Color(l.zip_channels(&r, |a, b| apply(op, a, b))))
+        }
+        (Value::Percent(l), Value::Percent(r)) if matches!(op, BinaryOp::Add | BinaryOp::Sub) => {
+            Ok(Value::Percent(apply(op, l, r)))
+        }
+        (Value::Percent(p), Value::Number(n)) if matches!(op, BinaryOp::Mul | BinaryOp::Div) => {
+            Ok(Value::Percent(apply(op, p, n)))
+        }
+        (Value::Number(n), Value::Percent(p)) if op == BinaryOp::Mul => Ok(Value::Percent(n * p)),
+        (Value::Color(c), Value::Percent(p)) if matches!(op, BinaryOp::Mul | BinaryOp::Div) => {
+            Ok(Value::Color(c.map_channels(|ch| apply(op, ch, p / 100.0))))
+        }
+        (Value::Percent(p), Value::Color(c)) if op == BinaryOp::Mul => {
+            Ok(Value::Color(c.map_channels(|ch| ch * p / 100.0)))
+        }
+        (left, right) => Err(EvalFault::InvalidOperands {
+            op,
+            left: left.type_name(),
+            right: right.type_name(),
+        }),
+    }?;
+    check_finite(value)
+}
+
+pub(super) fn eval_negate(value: Value) -> Result<Value, EvalFault> {
+    let value = match value {
+        Value::Number(number) => Ok(Value::Number(-number)),
+        Value::Percent(number) => Ok(Value::Percent(-number)),
+        value => Err(EvalFault::InvalidNegation {
+            operand: value.type_name(),
+        }),
+    }?;
+    check_finite(value)
+}
+
+// infやNaNになった数は変数に入れずにエラーにする 色はチャンネルごとに丸められるので調べない
+pub(super) fn check_finite(value: Value) -> Result<Value, EvalFault> {
+    match value {
+        Value::Number(number) | Value::Percent(number) if !number.is_finite() => {
+            Err(EvalFault::NotFinite)
+        }
+        value => Ok(value),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{color::Color, parser::BinaryOp};
+
+    use super::{eval_binary_operation, eval_negate, EvalFault, Value};
+
+    #[test]
+    fn binary_operation() {
+        let color = || Value::Color(Color::new(100, 150, 200));
+
+        assert_eq!(
+            eval_binary_operation(BinaryOp::Div, Value::Number(3.0), Value::Number(2.0)),
+            Ok(Value::Number(1.5))
+        );
+        assert_eq!(
+            eval_binary_operation(BinaryOp::Mul, color(), Value::Number(0.8)),
+            Ok(Value::Color(Color::new(80, 120, 160)))
+        );
+        assert_eq!(
+            eval_binary_operation(BinaryOp::Add, Value::Number(100.0), color()),
+            Ok(Value::Color(Color::new(200, 250, 255)))
+        );
+        assert_eq!(
+            eval_binary_operation(
+                BinaryOp::Sub,
+                color(),
+                Value::Color(Color::new_rgba(150, 50, 0, 10))
+            ),
+            Ok(Value::Color(Color::new(0, 100, 200)))
+        );
+        assert_eq!(
+            eval_binary_operation(BinaryOp::Div, color(), Value::Number(0.0)),
+            Err(EvalFault::DivisionByZero)
+        );
+        assert_eq!(
+            eval_binary_operation(BinaryOp::Mul, color(), color()),
+            Err(EvalFault::InvalidOperands {
+                op: BinaryOp::Mul,
+                left: "color",
+                right: "color"
+            })
+        );
+        assert_eq!(
+            eval_binary_operation(BinaryOp::Sub, Value::Number(1.0), color()),
+            Err(EvalFault::InvalidOperands {
+                op: BinaryOp::Sub,
+                left: "number",
+                right: "color"
+            })
+        );
+    }
+
+    #[test]
+    fn not_finite() {
+        assert_eq!(
+            eval_binary_operation(BinaryOp::Mul, Value::Number(1e300), Value::Number(1e300)),
+            Err(EvalFault::NotFinite)
+        );
+        assert_eq!(
+            eval_binary_operation(BinaryOp::Mul, Value::Percent(1e300), Value::Number(1e300)),
+            Err(EvalFault::NotFinite)
+        );
+        assert_eq!(
+            eval_binary_operation(
+                BinaryOp::Sub,
+                Value::Number(f64::INFINITY),
+                Value::Number(f64::INFINITY)
+            ),
+            Err(EvalFault::NotFinite)
+        );
+        assert_eq!(
+            eval_negate(Value::Number(f64::NEG_INFINITY)),
+            Err(EvalFault::NotFinite)
+        );
+        assert_eq!(
+            eval_binary_operation(BinaryOp::Mul, Value::Number(1e300), Value::Number(-2.0)),
+            Ok(Value::Number(-2e300))
+        );
+    }
+
+    #[test]
+    fn percent_operation() {
+        let color = || Value::Color(Color::new(100, 150, 200));
+
+        assert_eq!(
+            eval_binary_operation(BinaryOp::Sub, Value::Percent(50.0), Value::Percent(20.0)),
+            Ok(Value::Percent(30.0))
+        );
+        assert_eq!(
+            eval_binary_operation(BinaryOp::Mul, Value::Number(2.0), Value::Percent(20.0)),
+            Ok(Value::Percent(40.0))
+        );
+        assert_eq!(
+            eval_binary_operation(BinaryOp::Mul, color(), Value::Percent(50.0)),
+            Ok(Value::Color(Color::new(50, 75, 100)))
+        );
+        assert_eq!(
+            eval_binary_operation(BinaryOp::Div, Value::Percent(50.0), Value::Number(0.0)),
+            Err(EvalFault::DivisionByZero)
+        );
+        assert_eq!(
+            eval_binary_operation(BinaryOp::Add, Value::Number(1.0), Value::Percent(20.0)),
+            Err(EvalFault::InvalidOperands {
+                op: BinaryOp::Add,
+                left: "number",
+                right: "percentage"
+            })
+        );
+    }
+
+    #[test]
+    fn negate() {
+        assert_eq!(eval_negate(Value::Number(2.0)), Ok(Value::Number(-2.0)));
+        assert_eq!(
+            eval_negate(Value::Color(Color::new(0, 0, 0))),
+            Err(EvalFault::InvalidNegation { operand: "color" })
+        );
+    }
+}
@@ -1,3 +1,57 @@
+use crate::location::Location;
+
 pub trait Fault {
     fn msg(&self) -> String;
+
+    // エラーの箇所の下に表示する短い説明
+    fn label(&self) -> Option<String> {
+        None
+    }
+
+    // 直し方のヒント
+    fn help(&self) -> Option<String> {
+        None
+    }
+
+    fn location(&self) -> Option<&Location> {
+        None
+    }
+
+    // `theme.dfr:12:8: ParseError: Syntax` の形式
+    fn report(&self) -> String {
+        match self.location() {
+            Some(location) => format!("{}: {}", location, self.msg()),
+            None => self.msg(),
+        }
+    }
+}
+
+// どのファイルのどこで起きたかが分かっているFault
+pub struct LocatedFault {
+    fault: Box<dyn Fault>,
+    location: Location,
+}
+
+impl LocatedFault {
+    pub fn new(fault: Box<dyn Fault>, location: Location) -> Self {
+        LocatedFault { fault, location }
+    }
+}
+
+impl Fault for LocatedFault {
+    fn msg(&self) -> String {
+        self.fault.msg()
+    }
+
+    fn label(&self) -> Option<String> {
+        self.fault.label()
+    }
+
+    fn help(&self) -> Option<String> {
+        self.fault.help()
+    }
+
+    fn location(&self) -> Option<&Location> {
+        Some(&self.location)
+    }
 }
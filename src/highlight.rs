@@ -0,0 +1,102 @@
+// エディタのハイライトグループ `highlight Normal.fg = fg;`で色を割り当てる
+
+use crate::color::Color;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum HighlightAttr {
+    Fg,
+    Bg,
+    // 下線の色
+    Sp,
+    // "bold,italic" のような文字列
+    Style,
+}
+
+impl HighlightAttr {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "fg" => Some(HighlightAttr::Fg),
+            "bg" => Some(HighlightAttr::Bg),
+            "sp" => Some(HighlightAttr::Sp),
+            "style" => Some(HighlightAttr::Style),
+            _ => None,
+        }
+    }
+}
+
+// `Normal.fg`や`@comment.style`をグループと属性に分ける
+pub fn split_highlight_target(name: &str) -> Option<(String, HighlightAttr)> {
+    let (group, attr) = name.rsplit_once('.')?;
+    if group.is_empty() || group == "@" {
+        return None;
+    }
+    Some((group.to_string(), HighlightAttr::from_name(attr)?))
+}
+
+pub const STYLES: [&str; 6] = [
+    "bold",
+    "italic",
+    "underline",
+    "undercurl",
+    "strikethrough",
+    "reverse",
+];
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Highlight {
+    pub group: String,
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub sp: Option<Color>,
+    // STYLESのどれか 定義された順
+    pub style: Vec<&'static str>,
+}
+
+// "bold,italic"や"bold italic"を分ける 知らないスタイルはErrで返す
+// "none"は何もつけない
+pub fn parse_style(text: &str) -> Result<Vec<&'static str>, String> {
+    let mut styles = Vec::new();
+    for word in text.split([',', ' ']).filter(|word| !word.is_empty()) {
+        if word == "none" {
+            continue;
+        }
+        match STYLES.iter().find(|style| **style == word) {
+            Some(style) if !styles.contains(style) => styles.push(*style),
+            Some(_) => {}
+            None => return Err(word.to_string()),
+        }
+    }
+    Ok(styles)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_style, split_highlight_target, HighlightAttr};
+
+    #[test]
+    fn _split_highlight_target() {
+        assert_eq!(
+            split_highlight_target("Normal.fg"),
+            Some(("Normal".to_string(), HighlightAttr::Fg))
+        );
+        assert_eq!(
+            split_highlight_target("@string.special.style"),
+            Some(("@string.special".to_string(), HighlightAttr::Style))
+        );
+        assert_eq!(
+            split_highlight_target("UI.bg"),
+            Some(("UI".to_string(), HighlightAttr::Bg))
+        );
+        assert_eq!(split_highlight_target("Blue.500"), None);
+        assert_eq!(split_highlight_target("Normal"), None);
+        assert_eq!(split_highlight_target("@.fg"), None);
+    }
+
+    #[test]
+    fn _parse_style() {
+        assert_eq!(parse_style("bold,italic"), Ok(vec!["bold", "italic"]));
+        assert_eq!(parse_style("bold italic bold"), Ok(vec!["bold", "italic"]));
+        assert_eq!(parse_style("none"), Ok(vec![]));
+        assert_eq!(parse_style("bold,blink"), Err("blink".to_string()));
+    }
+}
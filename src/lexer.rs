@@ -1,20 +1,9 @@
 use crate::{
-    color::{self, Color},
+    color::Color,
     fault,
-    utils::peek_take_while,
+    location::{Position, Span, Spanned},
 };
-use std::collections::VecDeque;
-
-fn pops_front<T>(iter: &mut VecDeque<T>, length: usize) -> Vec<T> {
-    let mut ret_vec = Vec::with_capacity(length);
-    for _ in 0..length {
-        let Some(item) = iter.pop_front() else {
-            break;
-        };
-        ret_vec.push(item);
-    }
-    ret_vec
-}
+use std::{collections::VecDeque, fmt};
 
 fn is_token_char(ch: char) -> bool {
     if ch == '#' {
@@ -32,6 +21,19 @@ fn is_token_char(ch: char) -> bool {
     if ch == ',' {
         return true;
     }
+    if ch == ';' {
+        return true;
+    }
+    if ch == '>' || ch == '<' {
+        return true;
+    }
+    if ch == '"' {
+        return true;
+    }
+    // `-`は単語の途中では区切りにならない (`a-b`は1つの識別子) 数の直後はtake_wordで区切る
+    if ch == '+' || ch == '*' || ch == '/' {
+        return true;
+    }
     false
 }
 
@@ -49,121 +51,455 @@ fn is_skip_char(ch: char) -> bool {
     false
 }
 
-#[derive(Debug)]
+// `10` `-10` `0.5` `-0.5` のような形式
+// 負の数は`-`と数字の2つのトークンになる
+fn is_number_literal(word: &str) -> bool {
+    let (int_part, fract_part) = match word.split_once('.') {
+        Some((int_part, fract_part)) => (int_part, Some(fract_part)),
+        None => (word, None),
+    };
+
+    let is_digits =
+        |digits: &str| !digits.is_empty() && digits.chars().all(|ch| ch.is_ascii_digit());
+
+    is_digits(int_part) && fract_part.is_none_or(is_digits)
+}
+
+// `1e3` `.5` `1.` のように数字と`.`と`e`だけでできた単語
+// 数として読めなければ識別子ではなくエラーにする
+fn looks_like_number(word: &str) -> bool {
+    word.contains(|ch: char| ch.is_ascii_digit())
+        && word
+            .chars()
+            .all(|ch| ch.is_ascii_digit() || matches!(ch, '.' | 'e' | 'E'))
+        && !word.starts_with(['e', 'E'])
+}
+
+#[derive(Debug, PartialEq)]
 pub enum LexFault {
     Value,
+    InvalidNumber(String),
+    NumberOutOfRange,
+    UnterminatedComment,
+    UnterminatedString,
+    InvalidEscape(String),
 }
 impl fault::Fault for LexFault {
     fn msg(&self) -> String {
+        match self {
+            LexFault::Value => "LexError: value error".to_string(),
+            LexFault::InvalidNumber(word) => format!("LexError: invalid number literal `{}`", word),
+            LexFault::NumberOutOfRange => "LexError: number literal is too large".to_string(),
+            LexFault::UnterminatedComment => "LexError: unterminated block comment".to_string(),
+            LexFault::UnterminatedString => "LexError: unterminated string".to_string(),
+            LexFault::InvalidEscape(escape) => {
+                format!("LexError: unknown escape `{}` in string", escape)
+            }
+        }
+    }
+
+    fn label(&self) -> Option<String> {
+        match self {
+            LexFault::Value => Some("invalid color literal".to_string()),
+            LexFault::InvalidNumber(_) => Some("invalid number".to_string()),
+            LexFault::NumberOutOfRange => Some("out of range".to_string()),
+            LexFault::UnterminatedComment => Some("comment starts here".to_string()),
+            LexFault::UnterminatedString => Some("string starts here".to_string()),
+            LexFault::InvalidEscape(_) => Some("invalid escape".to_string()),
+        }
+    }
+
+    fn help(&self) -> Option<String> {
         match self {
             LexFault::Value => {
-                format!("LexError: value error")
+                Some("hex colors are written with 3, 4, 6 or 8 hex digits".to_string())
+            }
+            LexFault::InvalidNumber(_) => {
+                Some("numbers are written like `10`, `0.5` or `50%`".to_string())
             }
+            LexFault::NumberOutOfRange => {
+                Some("numbers must be smaller than about 1.8e308".to_string())
+            }
+            LexFault::UnterminatedComment => {
+                Some("close the comment with `*/`; block comments can be nested".to_string())
+            }
+            LexFault::UnterminatedString => Some(
+                "close the string with `\"` on the same line; use `\\n` for a newline".to_string(),
+            ),
+            LexFault::InvalidEscape(_) => Some(
+                "supported escapes are `\\\"`, `\\\\`, `\\n`, `\\r`, `\\t` and `\\u{...}`"
+                    .to_string(),
+            ),
         }
     }
 }
 
-// TODO refactor
-fn remove_comment_line(chars: &mut VecDeque<char>) -> VecDeque<char> {
-    let mut ret_chars = VecDeque::new();
+// 現在位置を数えながら1文字ずつ読み進める
+struct Cursor<'a> {
+    chars: &'a mut VecDeque<char>,
+    position: Position,
+}
 
-    let mut old_char = match chars.pop_front() {
-        Some(c) => c,
-        None => {
-            return ret_chars;
-        }
-    };
+impl Cursor<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.front().copied()
+    }
 
-    loop {
-        let Some(current) = chars.pop_front() else {
-            ret_chars.push_back(old_char);
-            break;
-        };
+    fn next(&mut self) -> Option<char> {
+        let ch = self.chars.pop_front()?;
+        self.position.advance(ch);
+        Some(ch)
+    }
 
-        if old_char == '/' && current == '/' {
-            peek_take_while(chars, |&ch| ch == '\n');
+    fn starts_with(&self, prefix: &str) -> bool {
+        prefix
+            .chars()
+            .enumerate()
+            .all(|(i, ch)| self.chars.get(i) == Some(&ch))
+    }
 
-            match chars.pop_front() {
-                Some(c) => old_char = c,
-                None => {
-                    break;
-                }
-            }
-        } else {
-            ret_chars.push_back(old_char);
-            old_char = current;
+    fn skip(&mut self, n: usize) {
+        for _ in 0..n {
+            self.next();
         }
     }
 
-    ret_chars
-}
+    fn is_comment_start(&self) -> bool {
+        self.starts_with("//") || self.starts_with("/*")
+    }
 
-pub fn lexer(chars: &mut VecDeque<char>) -> Result<VecDeque<Token>, LexFault> {
-    let mut chars = remove_comment_line(chars);
-    let mut tokens = VecDeque::new();
+    // `///`で始まり、`////`で始まらない行
+    fn is_doc_comment_start(&self) -> bool {
+        self.starts_with("///") && !self.starts_with("////")
+    }
 
-    loop {
-        let Some(ch) = chars.pop_front() else {
-            break;
-        };
+    fn skip_comment_line(&mut self) {
+        while let Some(ch) = self.next() {
+            if ch == '\n' {
+                break;
+            }
+        }
+    }
 
-        if is_skip_char(ch) {
-            continue;
+    // 改行の手前まで読み、`///`と直後の空白1つを取り除く
+    fn take_doc_comment(&mut self) -> String {
+        self.skip(3);
+        if self.peek() == Some(' ') {
+            self.next();
+        }
+        let mut text = String::new();
+        while let Some(ch) = self.peek() {
+            if ch == '\n' {
+                break;
+            }
+            text.push(ch);
+            self.next();
         }
+        text.trim_end().to_string()
+    }
 
-        if ch == '=' {
-            tokens.push_back(Token::Assign);
-            continue;
+    // `/* /* */ */` のように入れ子にできる
+    // 閉じられていない場合はfalse
+    fn skip_block_comment(&mut self) -> bool {
+        let mut depth = 0;
+        loop {
+            if self.starts_with("/*") {
+                self.skip(2);
+                depth += 1;
+            } else if self.starts_with("*/") {
+                self.skip(2);
+                depth -= 1;
+                if depth == 0 {
+                    return true;
+                }
+            } else if self.next().is_none() {
+                return false;
+            }
         }
-        if ch == '(' {
-            tokens.push_back(Token::LeftPare);
-            continue;
+    }
+
+    // 開始の`"`の後から閉じる`"`までを読む 文字列は改行をまたげない
+    // 不明なエスケープがあっても閉じる`"`までは読み進め、後ろの文を壊さないようにする
+    fn take_string(&mut self) -> Result<String, LexFault> {
+        let mut text = String::new();
+        let mut fault = None;
+        loop {
+            let ch = match self.peek() {
+                None | Some('\n') => return Err(LexFault::UnterminatedString),
+                Some(ch) => ch,
+            };
+            self.next();
+            match ch {
+                '"' => break,
+                '\\' => match self.take_escape() {
+                    Ok(ch) => text.push(ch),
+                    Err(escape) => {
+                        fault.get_or_insert(LexFault::InvalidEscape(escape));
+                    }
+                },
+                ch => text.push(ch),
+            }
         }
-        if ch == ')' {
-            tokens.push_back(Token::RightPare);
-            continue;
+
+        match fault {
+            Some(fault) => Err(fault),
+            None => Ok(text),
         }
-        if ch == ',' {
-            tokens.push_back(Token::Comma);
-            continue;
+    }
+
+    // `\`の後を読む 失敗した場合はエスケープ全体の文字列を返す
+    fn take_escape(&mut self) -> Result<char, String> {
+        let ch = match self.peek() {
+            None | Some('\n') => return Err("\\".to_string()),
+            Some(ch) => ch,
+        };
+        self.next();
+        match ch {
+            '"' => Ok('"'),
+            '\\' => Ok('\\'),
+            'n' => Ok('\n'),
+            'r' => Ok('\r'),
+            't' => Ok('\t'),
+            'u' => {
+                if self.peek() != Some('{') {
+                    return Err("\\u".to_string());
+                }
+                self.next();
+                let hex = self.take_until(|ch| !ch.is_ascii_hexdigit());
+                if self.peek() != Some('}') {
+                    return Err(format!("\\u{{{}", hex));
+                }
+                self.next();
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or(format!("\\u{{{}}}", hex))
+            }
+            ch => Err(format!("\\{}", ch)),
         }
+    }
 
-        if ch == '#' {
-            let hex = pops_front(&mut chars, 6);
-            if hex.len() != 6 {
-                return Err(LexFault::Value);
+    // checkがtrueを返すか、コメントが始まるまで読む
+    fn take_until(&mut self, check: fn(char) -> bool) -> String {
+        let mut ret = String::new();
+        while let Some(ch) = self.peek() {
+            if check(ch) || self.is_comment_start() {
+                break;
             }
-            let mut hex_iter = hex.into_iter();
-            let Some(color) = Color::from_hex_chars(&mut hex_iter) else {
-                return Err(LexFault::Value);
-            };
-            tokens.push_back(Token::HexColor(color));
-            continue;
+            ret.push(ch);
+            self.next();
+        }
+        ret
+    }
+
+    // firstから始まる単語を読む
+    // `a-b`は1つの名前だが、`100-10`や`50%-10%`のように数の直後の`-`は引き算にする
+    fn take_word(&mut self, first: char) -> String {
+        let mut word = first.to_string();
+        while let Some(ch) = self.peek() {
+            if is_skip_char(ch) || is_token_char(ch) || self.is_comment_start() {
+                break;
+            }
+            if ch == '-' && is_number_literal(word.strip_suffix('%').unwrap_or(&word)) {
+                break;
+            }
+            word.push(ch);
+            self.next();
         }
+        word
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
+
+// 読めなかったトークンはErrとして残し、その後も読み続ける
+// どの文が失敗したかはparserが`;`で区切って判断する
+pub type LexedToken = Result<SpannedToken, Spanned<LexFault>>;
+
+pub fn lexer(chars: &mut VecDeque<char>) -> VecDeque<LexedToken> {
+    let mut cursor = Cursor {
+        chars,
+        position: Position::start(),
+    };
+    let mut tokens = VecDeque::new();
 
-        let word_vec = peek_take_while(&mut chars, |&ch| is_skip_char(ch) || is_token_char(ch));
+    loop {
+        let token_start = cursor.position;
 
-        let word: String = format!("{}{}", ch, word_vec.into_iter().collect::<String>());
+        // 文の途中の`///`は普通のコメントとして読み飛ばす
+        let statement_start = matches!(
+            tokens.back(),
+            None | Some(Ok(SpannedToken {
+                token: Token::Semicolon | Token::DocComment(_),
+                ..
+            }))
+        );
 
-        if word == "let" {
-            tokens.push_back(Token::Let);
+        let token = if statement_start && cursor.is_doc_comment_start() {
+            Ok(Some(Token::DocComment(cursor.take_doc_comment())))
+        } else if cursor.starts_with("//") {
+            cursor.skip_comment_line();
             continue;
+        } else if cursor.starts_with("/*") {
+            if cursor.skip_block_comment() {
+                continue;
+            }
+            Err(LexFault::UnterminatedComment)
+        } else {
+            let after_include = matches!(
+                tokens.back(),
+                Some(Ok(SpannedToken {
+                    token: Token::Include,
+                    ..
+                }))
+            );
+            let Some(ch) = cursor.next() else {
+                break;
+            };
+            lex_token(ch, &mut cursor, after_include)
+        };
+        let span = Span::new(token_start, cursor.position);
+
+        match token {
+            Ok(Some(token)) => tokens.push_back(Ok(SpannedToken { token, span })),
+            Ok(None) => continue,
+            Err(fault) => tokens.push_back(Err(Spanned::new(fault, span))),
         }
+    }
 
-        if word == "include" {
-            tokens.push_back(Token::Include);
-            continue;
+    tokens
+}
+
+// chから始まるトークンを1つ読む 空白の場合はNone
+fn lex_token(
+    ch: char,
+    cursor: &mut Cursor,
+    after_include: bool,
+) -> Result<Option<Token>, LexFault> {
+    if is_skip_char(ch) {
+        return Ok(None);
+    }
+
+    // `include /abs/path` の絶対パスを割り算と区別する
+    if ch == '/' && after_include {
+        let path = cursor.take_until(|ch| is_skip_char(ch) || ch == ';');
+        return Ok(Some(Token::Identifier(format!("{}{}", ch, path))));
+    }
+
+    // 単語の先頭の`-`は演算子
+    if ch == '+' {
+        return Ok(Some(Token::Plus));
+    }
+    if ch == '-' {
+        return Ok(Some(Token::Minus));
+    }
+    if ch == '*' {
+        return Ok(Some(Token::Star));
+    }
+    if ch == '/' {
+        return Ok(Some(Token::Slash));
+    }
+
+    if ch == '=' {
+        return Ok(Some(Token::Assign));
+    }
+    if ch == '(' {
+        return Ok(Some(Token::LeftPare));
+    }
+    if ch == ')' {
+        return Ok(Some(Token::RightPare));
+    }
+    if ch == ',' {
+        return Ok(Some(Token::Comma));
+    }
+    if ch == ';' {
+        return Ok(Some(Token::Semicolon));
+    }
+
+    if ch == '>' || ch == '<' {
+        let or_equal = cursor.peek() == Some('=');
+        if or_equal {
+            cursor.next();
         }
+        let token = match (ch, or_equal) {
+            ('>', false) => Token::Greater,
+            ('>', true) => Token::GreaterEqual,
+            ('<', false) => Token::Less,
+            _ => Token::LessEqual,
+        };
+        return Ok(Some(token));
+    }
 
-        if let Ok(int) = word.parse::<color::ColorInt>() {
-            tokens.push_back(Token::Int(int));
-            continue;
+    if ch == '"' {
+        return cursor.take_string().map(|text| Some(Token::Str(text)));
+    }
+
+    if ch == '#' {
+        let hex = cursor.take_until(|ch| !ch.is_ascii_hexdigit());
+        let Some(color) = Color::from_hex_chars(&mut hex.chars()) else {
+            return Err(LexFault::Value);
+        };
+        return Ok(Some(Token::HexColor(color)));
+    }
+
+    // `include ./foo.dfr` のパスの途中の`/`は区切りにならない
+    let word = match after_include {
+        true => {
+            let rest = cursor.take_until(|ch| is_skip_char(ch) || (is_token_char(ch) && ch != '/'));
+            format!("{}{}", ch, rest)
         }
+        false => cursor.take_word(ch),
+    };
+
+    if word == "let" {
+        return Ok(Some(Token::Let));
+    }
 
-        tokens.push_back(Token::Identifier(word))
+    if word == "palette" {
+        return Ok(Some(Token::Palette));
     }
 
-    Ok(tokens)
+    if word == "assert" {
+        return Ok(Some(Token::Assert));
+    }
+
+    if word == "include" {
+        return Ok(Some(Token::Include));
+    }
+
+    if word == "fn" {
+        return Ok(Some(Token::Fn));
+    }
+
+    if word == "highlight" {
+        return Ok(Some(Token::Highlight));
+    }
+
+    // `50%`
+    let (digits, percent) = match word.strip_suffix('%') {
+        Some(digits) => (digits, true),
+        None => (word.as_str(), false),
+    };
+    if is_number_literal(digits) {
+        let number: f64 = digits.parse().expect("bug");
+        if !number.is_finite() {
+            return Err(LexFault::NumberOutOfRange);
+        }
+        return Ok(Some(match percent {
+            true => Token::Percent(number),
+            false => Token::Number(number),
+        }));
+    }
+    // includeの後は`2024.dfr`のようなパスもあるので識別子にする
+    if looks_like_number(digits) && !after_include {
+        return Err(LexFault::InvalidNumber(word));
+    }
+
+    Ok(Some(Token::Identifier(word)))
 }
 
 #[derive(Debug, PartialEq)]
@@ -173,24 +509,84 @@ pub enum Token {
     // Const,
     // ConstIfNotExists,
     Include,
+    Assert,
+    Palette,
+    Fn,
+    Highlight,
     HexColor(Color),
     Identifier(String), // 標準搭載された関数も含める
-    Int(color::ColorInt),
+    Str(String),
+    Number(f64),
+    Percent(f64),
     Assign,
     LeftPare,
     RightPare,
     Comma,
+    Semicolon,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    DocComment(String),
+}
+
+// エラーメッセージ用
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Let => write!(f, "`let`"),
+            Token::Include => write!(f, "`include`"),
+            Token::Assert => write!(f, "`assert`"),
+            Token::Palette => write!(f, "`palette`"),
+            Token::Fn => write!(f, "`fn`"),
+            Token::Highlight => write!(f, "`highlight`"),
+            Token::HexColor(color) => write!(f, "color `{}`", color.to_hex_string()),
+            Token::Identifier(name) => write!(f, "identifier `{}`", name),
+            Token::Str(text) => write!(f, "string `{:?}`", text),
+            Token::Number(number) => write!(f, "number `{}`", number),
+            Token::Percent(number) => write!(f, "percentage `{}%`", number),
+            Token::Assign => write!(f, "`=`"),
+            Token::LeftPare => write!(f, "`(`"),
+            Token::RightPare => write!(f, "`)`"),
+            Token::Comma => write!(f, "`,`"),
+            Token::Semicolon => write!(f, "`;`"),
+            Token::Plus => write!(f, "`+`"),
+            Token::Minus => write!(f, "`-`"),
+            Token::Star => write!(f, "`*`"),
+            Token::Slash => write!(f, "`/`"),
+            Token::Greater => write!(f, "`>`"),
+            Token::GreaterEqual => write!(f, "`>=`"),
+            Token::Less => write!(f, "`<`"),
+            Token::LessEqual => write!(f, "`<=`"),
+            Token::DocComment(_) => write!(f, "doc comment"),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use std::collections::VecDeque;
+
     use crate::{
         color::Color,
-        lexer::{lexer, Token},
+        lexer::{lexer, LexFault, Token},
+        location::{Position, Span},
     };
+
+    fn lexer_tokens(chars: &mut VecDeque<char>) -> Result<Vec<Token>, LexFault> {
+        lexer(chars)
+            .into_iter()
+            .map(|token| token.map(|t| t.token).map_err(|fault| fault.inner))
+            .collect()
+    }
+
     fn test_lexer(line: &str, assert: Vec<Token>) {
         let mut test = line.chars().collect();
-        let parsed = Vec::from(lexer(&mut test).unwrap());
+        let parsed = lexer_tokens(&mut test).unwrap();
         assert_eq!(parsed, assert);
     }
 
@@ -214,7 +610,10 @@ mod test {
             ],
         );
 
-        test_lexer("hello/", vec![Token::Identifier("hello/".to_string())]);
+        test_lexer(
+            "hello/",
+            vec![Token::Identifier("hello".to_string()), Token::Slash],
+        );
 
         test_lexer("hello//", vec![Token::Identifier("hello".to_string())]);
 
@@ -236,12 +635,12 @@ mod test {
             vec![
                 Token::Include,
                 Token::Identifier("/".to_string()),
-                Token::Identifier("/".to_string()),
+                Token::Slash,
             ],
         );
 
         let mut test = "()=,".chars().collect();
-        let parsed = Vec::from(lexer(&mut test).unwrap());
+        let parsed = lexer_tokens(&mut test).unwrap();
         assert_eq!(
             parsed,
             vec![
@@ -252,8 +651,49 @@ mod test {
             ]
         );
 
+        let mut test = "palette palettes".chars().collect();
+        let parsed = lexer_tokens(&mut test).unwrap();
+        assert_eq!(
+            parsed,
+            vec![Token::Palette, Token::Identifier("palettes".to_string())]
+        );
+
+        let mut test = "highlight highlights".chars().collect();
+        let parsed = lexer_tokens(&mut test).unwrap();
+        assert_eq!(
+            parsed,
+            vec![
+                Token::Highlight,
+                Token::Identifier("highlights".to_string())
+            ]
+        );
+
+        let mut test = "assert a>=b c<d e>f g<=h >== assertion".chars().collect();
+        let parsed = lexer_tokens(&mut test).unwrap();
+        assert_eq!(
+            parsed,
+            vec![
+                Token::Assert,
+                Token::Identifier("a".to_string()),
+                Token::GreaterEqual,
+                Token::Identifier("b".to_string()),
+                Token::Identifier("c".to_string()),
+                Token::Less,
+                Token::Identifier("d".to_string()),
+                Token::Identifier("e".to_string()),
+                Token::Greater,
+                Token::Identifier("f".to_string()),
+                Token::Identifier("g".to_string()),
+                Token::LessEqual,
+                Token::Identifier("h".to_string()),
+                Token::GreaterEqual,
+                Token::Assign,
+                Token::Identifier("assertion".to_string()),
+            ]
+        );
+
         let mut test = "let hello = #ffffff".chars().collect();
-        let parsed = Vec::from(lexer(&mut test).unwrap());
+        let parsed = lexer_tokens(&mut test).unwrap();
         assert_eq!(
             parsed,
             vec![
@@ -265,7 +705,7 @@ mod test {
         );
 
         let mut test = "     let    hello    = \n \t  #ffffff".chars().collect();
-        let parsed = Vec::from(lexer(&mut test).unwrap());
+        let parsed = lexer_tokens(&mut test).unwrap();
         assert_eq!(
             parsed,
             vec![
@@ -277,7 +717,7 @@ mod test {
         );
 
         let mut test = "let hello=color1".chars().collect();
-        let parsed = Vec::from(lexer(&mut test).unwrap());
+        let parsed = lexer_tokens(&mut test).unwrap();
         assert_eq!(
             parsed,
             vec![
@@ -289,7 +729,7 @@ mod test {
         );
 
         let mut test = "_hello==letaaa".chars().collect();
-        let parsed = Vec::from(lexer(&mut test).unwrap());
+        let parsed = lexer_tokens(&mut test).unwrap();
         assert_eq!(
             parsed,
             vec![
@@ -300,41 +740,59 @@ mod test {
             ]
         );
 
-        let mut test = "hello#101010aaa=aaa".chars().collect();
-        let parsed = Vec::from(lexer(&mut test).unwrap());
+        let mut test = "hello#101010aa=aaa".chars().collect();
+        let parsed = lexer_tokens(&mut test).unwrap();
         assert_eq!(
             parsed,
             vec![
                 Token::Identifier("hello".to_string()),
-                Token::HexColor(Color::new(16, 16, 16)),
-                Token::Identifier("aaa".to_string()),
+                Token::HexColor(Color::new_rgba(16, 16, 16, 0xaa)),
                 Token::Assign,
                 Token::Identifier("aaa".to_string()),
             ]
         );
 
+        let mut test = "#abc #abcd #102030 #10203040 #102030/ggg".chars().collect();
+        let parsed = lexer_tokens(&mut test).unwrap();
+        assert_eq!(
+            parsed,
+            vec![
+                Token::HexColor(Color::new(0xaa, 0xbb, 0xcc)),
+                Token::HexColor(Color::new_rgba(0xaa, 0xbb, 0xcc, 0xdd)),
+                Token::HexColor(Color::new(16, 32, 48)),
+                Token::HexColor(Color::new_rgba(16, 32, 48, 64)),
+                Token::HexColor(Color::new(16, 32, 48)),
+                Token::Slash,
+                Token::Identifier("ggg".to_string()),
+            ]
+        );
+
+        let mut test = "hello#101010aaa=aaa".chars().collect();
+        let parsed = lexer_tokens(&mut test);
+        assert!(parsed.is_err());
+
         let mut test = "#".chars().collect();
-        let parsed = lexer(&mut test);
+        let parsed = lexer_tokens(&mut test);
         assert!(parsed.is_err());
 
         let mut test = "#aa".chars().collect();
-        let parsed = lexer(&mut test);
+        let parsed = lexer_tokens(&mut test);
         assert!(parsed.is_err());
 
         let mut test = "#gg".chars().collect();
-        let parsed = lexer(&mut test);
+        let parsed = lexer_tokens(&mut test);
         assert!(parsed.is_err());
 
         let mut test = "aaaa#aaaaa".chars().collect();
-        let parsed = lexer(&mut test);
+        let parsed = lexer_tokens(&mut test);
         assert!(parsed.is_err());
 
         let mut test = "aaaa#aa".chars().collect();
-        let parsed = lexer(&mut test);
+        let parsed = lexer_tokens(&mut test);
         assert!(parsed.is_err());
 
         let mut test = "((((()))))aaa((((()))))".chars().collect();
-        let parsed = Vec::from(lexer(&mut test).unwrap());
+        let parsed = lexer_tokens(&mut test).unwrap();
         assert_eq!(
             parsed,
             vec![
@@ -362,7 +820,7 @@ mod test {
             ]
         );
         let mut test = "let aaa = hello(aaa)".chars().collect();
-        let parsed = Vec::from(lexer(&mut test).unwrap());
+        let parsed = lexer_tokens(&mut test).unwrap();
         assert_eq!(
             parsed,
             vec![
@@ -377,7 +835,7 @@ mod test {
         );
 
         let mut test = "let( )  aaa()   ( aaa )".chars().collect();
-        let parsed = Vec::from(lexer(&mut test).unwrap());
+        let parsed = lexer_tokens(&mut test).unwrap();
         assert_eq!(
             parsed,
             vec![
@@ -393,7 +851,7 @@ mod test {
             ]
         );
         let mut test = "let()aaa()(aaa)".chars().collect();
-        let parsed = Vec::from(lexer(&mut test).unwrap());
+        let parsed = lexer_tokens(&mut test).unwrap();
         assert_eq!(
             parsed,
             vec![
@@ -409,40 +867,65 @@ mod test {
             ]
         );
 
-        let mut test = "a-b 100-10".chars().collect();
-        let parsed = Vec::from(lexer(&mut test).unwrap());
+        let mut test = "a-b 100-10 50%-a 1-2-c".chars().collect();
+        let parsed = lexer_tokens(&mut test).unwrap();
         assert_eq!(
             parsed,
             vec![
                 Token::Identifier("a-b".to_string()),
-                Token::Identifier("100-10".to_string()),
+                Token::Number(100.0),
+                Token::Minus,
+                Token::Number(10.0),
+                Token::Percent(50.0),
+                Token::Minus,
+                Token::Identifier("a".to_string()),
+                Token::Number(1.0),
+                Token::Minus,
+                Token::Number(2.0),
+                Token::Minus,
+                Token::Identifier("c".to_string()),
             ]
         );
 
         let mut test = "255 256 0 -1".chars().collect();
-        let parsed = Vec::from(lexer(&mut test).unwrap());
+        let parsed = lexer_tokens(&mut test).unwrap();
         assert_eq!(
             parsed,
             vec![
-                Token::Int(255),
-                Token::Identifier("256".to_string()),
-                Token::Int(0),
-                Token::Identifier("-1".to_string()),
+                Token::Number(255.0),
+                Token::Number(256.0),
+                Token::Number(0.0),
+                Token::Minus,
+                Token::Number(1.0),
+            ]
+        );
+
+        let mut test = "0.5 -0.25 - -a".chars().collect();
+        let parsed = lexer_tokens(&mut test).unwrap();
+        assert_eq!(
+            parsed,
+            vec![
+                Token::Number(0.5),
+                Token::Minus,
+                Token::Number(0.25),
+                Token::Minus,
+                Token::Minus,
+                Token::Identifier("a".to_string()),
             ]
         );
 
         let mut test = "a(1,3,4,hello,  aaa,a-b, \n aaa\na,s)".chars().collect();
-        let parsed = Vec::from(lexer(&mut test).unwrap());
+        let parsed = lexer_tokens(&mut test).unwrap();
         assert_eq!(
             parsed,
             vec![
                 Token::Identifier("a".to_string()),
                 Token::LeftPare,
-                Token::Int(1),
+                Token::Number(1.0),
                 Token::Comma,
-                Token::Int(3),
+                Token::Number(3.0),
                 Token::Comma,
-                Token::Int(4),
+                Token::Number(4.0),
                 Token::Comma,
                 Token::Identifier("hello".to_string()),
                 Token::Comma,
@@ -459,7 +942,7 @@ mod test {
         );
 
         let mut test = "let a10 = rgb(10) 100 a0 0xa0 4294967297".chars().collect();
-        let parsed = Vec::from(lexer(&mut test).unwrap());
+        let parsed = lexer_tokens(&mut test).unwrap();
         assert_eq!(
             parsed,
             vec![
@@ -468,13 +951,236 @@ mod test {
                 Token::Assign,
                 Token::Identifier("rgb".to_string()),
                 Token::LeftPare,
-                Token::Int(10),
+                Token::Number(10.0),
                 Token::RightPare,
-                Token::Int(100),
+                Token::Number(100.0),
                 Token::Identifier("a0".to_string()),
                 Token::Identifier("0xa0".to_string()),
-                Token::Identifier("4294967297".to_string()),
+                Token::Number(4294967297.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn token_span() {
+        let mut test = "let a =\n  // comment;\n  #101010;".chars().collect();
+        let tokens = lexer(&mut test);
+        let spans: Vec<Span> = tokens.iter().map(|t| t.as_ref().unwrap().span).collect();
+        assert_eq!(
+            spans,
+            vec![
+                Span::new(Position::new(1, 1), Position::new(1, 4)),
+                Span::new(Position::new(1, 5), Position::new(1, 6)),
+                Span::new(Position::new(1, 7), Position::new(1, 8)),
+                Span::new(Position::new(3, 3), Position::new(3, 10)),
+                Span::new(Position::new(3, 10), Position::new(3, 11)),
             ]
         );
+
+        // 読めないトークンの後も続けて読む
+        let mut test = "a = #gg; b".chars().collect();
+        let tokens = lexer(&mut test);
+        assert_eq!(
+            tokens[2].as_ref().unwrap_err().span.start,
+            Position::new(1, 5)
+        );
+        assert_eq!(
+            tokens.back().unwrap().as_ref().unwrap().token,
+            Token::Identifier("b".to_string())
+        );
+    }
+
+    #[test]
+    fn semicolon() {
+        test_lexer(
+            "a = b; // c;d\nc",
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Assign,
+                Token::Identifier("b".to_string()),
+                Token::Semicolon,
+                Token::Identifier("c".to_string()),
+            ],
+        );
+        test_lexer(
+            "include ./foo.txt;",
+            vec![
+                Token::Include,
+                Token::Identifier("./foo.txt".to_string()),
+                Token::Semicolon,
+            ],
+        );
+    }
+
+    #[test]
+    fn block_and_doc_comment() {
+        test_lexer(
+            "a /* b /* c */ d */ = e",
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Assign,
+                Token::Identifier("e".to_string()),
+            ],
+        );
+        test_lexer(
+            "a/* b */=/**/e",
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Assign,
+                Token::Identifier("e".to_string()),
+            ],
+        );
+        test_lexer(
+            "/// Background color  \n///\n//// not doc\nbg",
+            vec![
+                Token::DocComment("Background color".to_string()),
+                Token::DocComment("".to_string()),
+                Token::Identifier("bg".to_string()),
+            ],
+        );
+        test_lexer(
+            "a = rgb(\n/// red\n10);\n/// b\nb",
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Assign,
+                Token::Identifier("rgb".to_string()),
+                Token::LeftPare,
+                Token::Number(10.0),
+                Token::RightPare,
+                Token::Semicolon,
+                Token::DocComment("b".to_string()),
+                Token::Identifier("b".to_string()),
+            ],
+        );
+
+        let mut test = "a = b; /* c /* d */".chars().collect();
+        assert_eq!(
+            lexer_tokens(&mut test).unwrap_err(),
+            LexFault::UnterminatedComment
+        );
+    }
+
+    #[test]
+    fn operator() {
+        test_lexer(
+            "accent*0.8+#101010 - (a-b)/-2",
+            vec![
+                Token::Identifier("accent".to_string()),
+                Token::Star,
+                Token::Number(0.8),
+                Token::Plus,
+                Token::HexColor(Color::new(0x10, 0x10, 0x10)),
+                Token::Minus,
+                Token::LeftPare,
+                Token::Identifier("a-b".to_string()),
+                Token::RightPare,
+                Token::Slash,
+                Token::Minus,
+                Token::Number(2.0),
+            ],
+        );
+        test_lexer(
+            "include /home/a-b/c.dfr; a / b",
+            vec![
+                Token::Include,
+                Token::Identifier("/home/a-b/c.dfr".to_string()),
+                Token::Semicolon,
+                Token::Identifier("a".to_string()),
+                Token::Slash,
+                Token::Identifier("b".to_string()),
+            ],
+        );
+        test_lexer(
+            "include ./theme/base.dfr; b/2 b-c",
+            vec![
+                Token::Include,
+                Token::Identifier("./theme/base.dfr".to_string()),
+                Token::Semicolon,
+                Token::Identifier("b".to_string()),
+                Token::Slash,
+                Token::Number(2.0),
+                Token::Identifier("b-c".to_string()),
+            ],
+        );
+    }
+    #[test]
+    fn percent() {
+        test_lexer(
+            "50% 12.5% -10% 5%%",
+            vec![
+                Token::Percent(50.0),
+                Token::Percent(12.5),
+                Token::Minus,
+                Token::Percent(10.0),
+                Token::Identifier("5%%".to_string()),
+            ],
+        );
+    }
+
+    #[test]
+    fn invalid_number() {
+        for word in ["1e3", ".5", "1.", "1.2.3", "1e3%"] {
+            let mut test = word.chars().collect();
+            assert_eq!(
+                lexer_tokens(&mut test).unwrap_err(),
+                LexFault::InvalidNumber(word.to_string())
+            );
+        }
+
+        let mut test = format!("1{}", "0".repeat(400)).chars().collect();
+        assert_eq!(
+            lexer_tokens(&mut test).unwrap_err(),
+            LexFault::NumberOutOfRange
+        );
+        let mut test = format!("1{}%", "0".repeat(400)).chars().collect();
+        assert_eq!(
+            lexer_tokens(&mut test).unwrap_err(),
+            LexFault::NumberOutOfRange
+        );
+
+        test_lexer(
+            "include 2024.dfr",
+            vec![Token::Include, Token::Identifier("2024.dfr".to_string())],
+        );
+    }
+
+    #[test]
+    fn string() {
+        test_lexer(
+            r#"name = "Solarized (dark), v2; // not a comment";"#,
+            vec![
+                Token::Identifier("name".to_string()),
+                Token::Assign,
+                Token::Str("Solarized (dark), v2; // not a comment".to_string()),
+                Token::Semicolon,
+            ],
+        );
+        test_lexer(
+            r#"include "my theme.dfr""a\"b\\c\n\t\u{263a}""#,
+            vec![
+                Token::Include,
+                Token::Str("my theme.dfr".to_string()),
+                Token::Str("a\"b\\c\n\t\u{263a}".to_string()),
+            ],
+        );
+
+        let mut test = "a = \"abc\nb".chars().collect();
+        assert_eq!(
+            lexer_tokens(&mut test).unwrap_err(),
+            LexFault::UnterminatedString
+        );
+
+        // 不明なエスケープの後も閉じる`"`まで読み、後ろのトークンは残る
+        let mut chars = r#""a\qb\u{zz}"; c"#.chars().collect();
+        let tokens: Vec<_> = lexer(&mut chars).into_iter().collect();
+        assert_eq!(
+            tokens[0].as_ref().unwrap_err().inner,
+            LexFault::InvalidEscape("\\q".to_string())
+        );
+        assert_eq!(tokens[1].as_ref().unwrap().token, Token::Semicolon);
+        assert_eq!(
+            tokens[2].as_ref().unwrap().token,
+            Token::Identifier("c".to_string())
+        );
     }
 }
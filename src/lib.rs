@@ -0,0 +1,22 @@
+mod app_path;
+pub mod color;
+pub mod diagnostic;
+pub mod emit;
+mod engine;
+mod envroiment;
+mod eval;
+pub mod fault;
+pub mod highlight;
+mod lexer;
+pub mod location;
+mod named_color;
+mod parser;
+mod run;
+pub mod template;
+mod utils;
+pub mod value;
+
+pub use color::Color;
+pub use engine::Engine;
+pub use eval::builtin::{Builtin, Param, ParamType, BUILTINS};
+pub use value::Value;
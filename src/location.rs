@@ -0,0 +1,104 @@
+use std::fmt;
+
+// 行と列はどちらも1から始まる
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Self {
+        Position { line, column }
+    }
+
+    pub fn start() -> Self {
+        Position::new(1, 1)
+    }
+
+    pub fn advance(&mut self, ch: char) {
+        if ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position::start()
+    }
+}
+
+// startは含み、endは含まない
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Span { start, end }
+    }
+
+    // 長さ0の範囲
+    pub fn at(position: Position) -> Self {
+        Span::new(position, position)
+    }
+}
+
+// 何かにSpanをくっつけたもの
+// lexer、parser、evalが返すエラーにも使う
+#[derive(Debug, PartialEq)]
+pub struct Spanned<T> {
+    pub inner: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(inner: T, span: Span) -> Self {
+        Spanned { inner, span }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub file: String,
+    pub span: Span,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.file, self.span.start.line, self.span.start.column
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Location, Position, Span};
+
+    #[test]
+    fn advance() {
+        let mut position = Position::start();
+        for ch in "ab\nc".chars() {
+            position.advance(ch);
+        }
+        assert_eq!(position, Position::new(2, 2));
+    }
+
+    #[test]
+    fn display_location() {
+        let location = Location {
+            file: "theme.dfr".to_string(),
+            span: Span::new(Position::new(12, 8), Position::new(12, 9)),
+        };
+        assert_eq!(location.to_string(), "theme.dfr:12:8");
+    }
+}
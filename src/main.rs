@@ -1,47 +1,221 @@
-mod app_path;
-mod color;
-mod envroiment;
-mod eval;
-mod fault;
-mod lexer;
-mod parser;
-mod run;
-mod utils;
-
-use app_path::AbsFilePath;
-use clap::Parser;
-use envroiment::Envroiment;
-use run::run;
-use std::{
-    collections::VecDeque,
-    fs::{self, read_to_string},
-    path::PathBuf,
+use clap::{Parser, Subcommand};
+use defiro::{
+    diagnostic::{render, ColorChoice, ErrorFormat},
+    emit::{
+        AlacrittyEmitter, CssEmitter, Format, JsonColor, JsonEmitter, KittyEmitter, LessEmitter,
+        NeovimEmitter, ScssEmitter, VscodeEmitter, WeztermEmitter, WindowsTerminalEmitter,
+        XresourcesEmitter,
+    },
+    fault::LocatedFault,
+    location::Location,
+    Engine, BUILTINS,
 };
+use std::{fs, path::Path, process};
 
 #[derive(Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Args {
-    file_path: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(required = true)]
+    file_path: Option<String>,
+
+    #[command(flatten)]
+    eval: EvalArgs,
+
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+
+    // `--format=css`で変数を書くブロックのセレクタ
+    #[arg(long, default_value = ":root")]
+    css_selector: String,
+
+    // `--format=css`でカスタムプロパティの名前の前につける文字列
+    #[arg(long, default_value = "")]
+    css_prefix: String,
+
+    // `--format=windows-terminal`、`neovim`、`vscode`の配色の名前 省略するとファイル名になる
+    #[arg(long)]
+    theme_name: Option<String>,
+
+    // `///`コメントをJSONのdescriptionとして出力する
+    // CSS、SCSS、Lessでは常にコメントとして出力する
+    #[arg(long)]
+    with_docs: bool,
+
+    // JSONを改行とインデントつきで出力する
+    #[arg(long)]
+    pretty: bool,
+
+    // `ui.bg.hover`をJSONの入れ子のオブジェクトにする
+    #[arg(long)]
+    nested: bool,
+
+    // JSONでの色の書き方
+    #[arg(long, value_enum, default_value_t = JsonColor::Hex)]
+    json_value: JsonColor,
 }
 
-fn main() {
-    let args = Args::parse();
+// renderでも使うオプション
+#[derive(clap::Args, Debug)]
+struct EvalArgs {
+    // エラーに色をつけるかどうか
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    #[arg(long, value_enum, default_value_t = ErrorFormat::Human)]
+    error_format: ErrorFormat,
+
+    // `gray50`などX11の色の名前も使えるようにする
+    #[arg(long)]
+    x11_colors: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    // 組み込み関数の一覧をシグネチャつきで表示する
+    Builtins,
+    // テンプレートの`{{ name }}`を変数の値で置き換えて表示する
+    Render {
+        file_path: String,
+        template_path: String,
+        #[command(flatten)]
+        eval: EvalArgs,
+    },
+}
+
+// ファイルを評価する 読めなければ終了する
+fn eval_file(file_path: &str, args: &EvalArgs) -> Engine {
+    let mut engine = Engine::new();
+    if args.x11_colors {
+        engine.enable_x11_colors();
+    }
+    if let Err(err) = engine.eval_file(file_path) {
+        eprintln!("error: cannot read {}: {}", file_path, err);
+        process::exit(1);
+    }
+    engine
+}
+
+// 評価中のエラーを表示する エラーがあればtrue
+fn report_faults(engine: &Engine, args: &EvalArgs) -> bool {
+    let color = args.color.enabled();
+    for err in engine.faults() {
+        let source = err
+            .location()
+            .and_then(|location| engine.source(&location.file));
+        eprintln!("{}", render(err.as_ref(), args.error_format, color, source));
+    }
+    !engine.faults().is_empty()
+}
+
+// 評価中のエラーと同じく、カレントディレクトリの中のファイルは相対パスで表示する
+fn display_path(path: &str) -> String {
+    let (Ok(path), Ok(current_dir)) = (fs::canonicalize(path), std::env::current_dir()) else {
+        return path.to_string();
+    };
+    match path.strip_prefix(current_dir) {
+        Ok(relative) => relative.to_string_lossy().to_string(),
+        Err(_) => path.to_string_lossy().to_string(),
+    }
+}
 
-    let mut env = Envroiment::new();
+fn render_template(file_path: &str, template_path: &str, args: &EvalArgs) {
+    let engine = eval_file(file_path, args);
+    let template = match fs::read_to_string(template_path) {
+        Ok(template) => template,
+        Err(err) => {
+            eprintln!("error: cannot read {}: {}", template_path, err);
+            process::exit(1);
+        }
+    };
 
-    let file_path = PathBuf::from(&args.file_path);
-    let file_string = read_to_string(&file_path).unwrap();
+    let mut failed = false;
+    match engine.render_template(&template) {
+        Ok(output) => print!("{}", output),
+        Err(fault) => {
+            let location = Location {
+                file: display_path(template_path),
+                span: fault.span,
+            };
+            let fault = LocatedFault::new(Box::new(fault.inner), location);
+            let color = args.color.enabled();
+            eprintln!(
+                "{}",
+                render(&fault, args.error_format, color, Some(&template))
+            );
+            failed = true;
+        }
+    }
 
-    let file_chars: VecDeque<char> = file_string.chars().collect();
-    let tmp = fs::canonicalize(file_path).unwrap();
-    let abs_path = tmp.to_str().unwrap();
+    if report_faults(&engine, args) || failed {
+        process::exit(1);
+    }
+}
 
-    let abs_file_path = AbsFilePath::from_string(abs_path).unwrap();
+fn main() {
+    let args = Args::parse();
 
-    run(&mut env, file_chars, abs_file_path);
+    match &args.command {
+        Some(Command::Builtins) => {
+            for builtin in BUILTINS {
+                println!("{}", builtin);
+            }
+            return;
+        }
+        Some(Command::Render {
+            file_path,
+            template_path,
+            eval,
+        }) => {
+            render_template(file_path, template_path, eval);
+            return;
+        }
+        None => {}
+    }
+
+    let file_path = args.file_path.as_ref().expect("required by clap");
+    let engine = eval_file(file_path, &args.eval);
 
-    println!("{}", env.vars_json());
+    // 配色の名前 省略するとファイル名になる
+    let theme_name = args.theme_name.clone().unwrap_or_else(|| {
+        let stem = Path::new(file_path).file_stem().unwrap_or_default();
+        stem.to_string_lossy().to_string()
+    });
+    let output = match args.format {
+        Format::Json => engine.emit(&JsonEmitter {
+            with_docs: args.with_docs,
+            pretty: args.pretty,
+            nested: args.nested,
+            color: args.json_value,
+        }),
+        Format::Css => engine.emit(&CssEmitter {
+            selector: args.css_selector.clone(),
+            prefix: args.css_prefix.clone(),
+        }),
+        Format::Scss => engine.emit(&ScssEmitter),
+        Format::Less => engine.emit(&LessEmitter),
+        Format::Alacritty => engine.emit(&AlacrittyEmitter),
+        Format::Kitty => engine.emit(&KittyEmitter),
+        Format::Wezterm => engine.emit(&WeztermEmitter),
+        Format::Xresources => engine.emit(&XresourcesEmitter),
+        Format::WindowsTerminal => engine.emit(&WindowsTerminalEmitter { name: theme_name }),
+        Format::Neovim => engine.emit_colorscheme(&NeovimEmitter { name: theme_name }),
+        Format::Vscode => engine.emit_colorscheme(&VscodeEmitter { name: theme_name }),
+    };
+
+    let mut failed = false;
+    match output {
+        Ok(output) => println!("{}", output),
+        Err(fault) => {
+            let color = args.eval.color.enabled();
+            eprintln!("{}", render(&fault, args.eval.error_format, color, None));
+            failed = true;
+        }
+    }
 
-    for err in env.faults {
-        eprintln!("{}", err.msg());
+    if report_faults(&engine, &args.eval) || failed {
+        process::exit(1);
     }
 }
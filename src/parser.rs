@@ -1,22 +1,125 @@
 use crate::{
-    color::{Color, ColorInt},
+    color::Color,
     fault,
-    lexer::Token,
+    highlight::{split_highlight_target, HighlightAttr},
+    lexer::{LexFault, LexedToken, SpannedToken, Token},
+    location::{Position, Span, Spanned},
 };
 use std::collections::VecDeque;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Call {
     pub name: String,
     pub args: Vec<Expression>,
 }
 
-#[derive(Debug)]
-pub enum Expression {
-    Int(ColorInt),
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl BinaryOp {
+    fn from_token(token: &Token) -> Option<Self> {
+        match token {
+            Token::Plus => Some(BinaryOp::Add),
+            Token::Minus => Some(BinaryOp::Sub),
+            Token::Star => Some(BinaryOp::Mul),
+            Token::Slash => Some(BinaryOp::Div),
+            _ => None,
+        }
+    }
+
+    // (左の結合力, 右の結合力) 右を大きくして左結合にする
+    fn binding_power(&self) -> (u8, u8) {
+        match self {
+            BinaryOp::Add | BinaryOp::Sub => (1, 2),
+            BinaryOp::Mul | BinaryOp::Div => (3, 4),
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+        }
+    }
+}
+
+// 単項の`-`は掛け算よりも強く結合する
+const PREFIX_BINDING_POWER: u8 = 5;
+
+#[derive(Debug, Clone)]
+pub enum ExpressionKind {
+    Number(f64),
+    // 0から100 `50%`は50になる
+    Percent(f64),
+    Str(String),
     Color(Color),
     Identifier(String),
     Call(Call),
+    Binary {
+        op: BinaryOp,
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+    Negate(Box<Expression>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+
+#[cfg(test)]
+impl Expression {
+    fn decoy(kind: ExpressionKind) -> Self {
+        Expression {
+            kind,
+            span: Span::default(),
+        }
+    }
+
+    pub fn number(number: f64) -> Self {
+        Expression::decoy(ExpressionKind::Number(number))
+    }
+
+    pub fn string(text: &str) -> Self {
+        Expression::decoy(ExpressionKind::Str(text.to_string()))
+    }
+
+    pub fn percent(number: f64) -> Self {
+        Expression::decoy(ExpressionKind::Percent(number))
+    }
+
+    pub fn color(color: Color) -> Self {
+        Expression::decoy(ExpressionKind::Color(color))
+    }
+
+    pub fn identifier(name: String) -> Self {
+        Expression::decoy(ExpressionKind::Identifier(name))
+    }
+
+    pub fn call(call: Call) -> Self {
+        Expression::decoy(ExpressionKind::Call(call))
+    }
+
+    pub fn binary(op: BinaryOp, left: Expression, right: Expression) -> Self {
+        Expression::decoy(ExpressionKind::Binary {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        })
+    }
+
+    pub fn negate(operand: Expression) -> Self {
+        Expression::decoy(ExpressionKind::Negate(Box::new(operand)))
+    }
 }
 
 #[derive(Debug)]
@@ -30,47 +133,233 @@ pub struct IncludeStatement {
     pub path: String,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CompareOp {
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+}
+
+impl CompareOp {
+    fn from_token(token: &Token) -> Option<Self> {
+        match token {
+            Token::Greater => Some(CompareOp::Greater),
+            Token::GreaterEqual => Some(CompareOp::GreaterEqual),
+            Token::Less => Some(CompareOp::Less),
+            Token::LessEqual => Some(CompareOp::LessEqual),
+            _ => None,
+        }
+    }
+
+    pub fn compare(&self, left: f64, right: f64) -> bool {
+        match self {
+            CompareOp::Greater => left > right,
+            CompareOp::GreaterEqual => left >= right,
+            CompareOp::Less => left < right,
+            CompareOp::LessEqual => left <= right,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CompareOp::Greater => ">",
+            CompareOp::GreaterEqual => ">=",
+            CompareOp::Less => "<",
+            CompareOp::LessEqual => "<=",
+        }
+    }
+}
+
+// assert contrast(fg, bg) >= 4.5
+#[derive(Debug)]
+pub struct AssertStatement {
+    pub left: Expression,
+    pub op: CompareOp,
+    pub right: Expression,
+}
+
+// palette blue = ramp(base, 10)
 #[derive(Debug)]
-pub enum Statement {
+pub struct PaletteStatement {
+    pub name: String,
+    pub right: Expression,
+}
+
+// fn hover(c) = lighten(c, 8%)
+#[derive(Debug)]
+pub struct FunctionStatement {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: Expression,
+}
+
+// highlight Normal.fg = fg
+#[derive(Debug)]
+pub struct HighlightStatement {
+    pub group: String,
+    pub attr: HighlightAttr,
+    pub value: Expression,
+}
+
+#[derive(Debug)]
+pub enum StatementKind {
     Let(LetStatement),
+    Highlight(HighlightStatement),
+    Function(FunctionStatement),
     Include(IncludeStatement),
+    Assert(AssertStatement),
+    Palette(PaletteStatement),
+}
+
+#[derive(Debug)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+    // 文の前の`///`コメント 複数行の場合は改行でつなげる
+    pub doc: Option<String>,
 }
 
+// expectedは "`=`" や "an expression" のような説明
 #[derive(Debug, PartialEq)]
 pub enum ParseFault {
-    Syntax,
+    Unexpected { expected: String, found: Token },
+    UnexpectedEnd { expected: String },
+    TrailingToken { found: Token },
+    DuplicateParameter { name: String },
 }
 impl fault::Fault for ParseFault {
     fn msg(&self) -> String {
         match self {
-            ParseFault::Syntax => {
-                format!("ParseError: Syntax")
+            ParseFault::Unexpected { expected, found } => {
+                format!("ParseError: expected {}, found {}", expected, found)
+            }
+            ParseFault::UnexpectedEnd { expected } => {
+                format!(
+                    "ParseError: unexpected end of statement, expected {}",
+                    expected
+                )
+            }
+            ParseFault::TrailingToken { found } => {
+                format!(
+                    "ParseError: unexpected {} after the end of the statement",
+                    found
+                )
             }
+            ParseFault::DuplicateParameter { name } => {
+                format!(
+                    "ParseError: parameter `{}` is declared more than once",
+                    name
+                )
+            }
+        }
+    }
+
+    fn label(&self) -> Option<String> {
+        match self {
+            ParseFault::Unexpected { expected, .. } => Some(format!("expected {}", expected)),
+            ParseFault::UnexpectedEnd { .. } => Some("statement ends here".to_string()),
+            ParseFault::TrailingToken { .. } => Some("unexpected token".to_string()),
+            ParseFault::DuplicateParameter { .. } => Some("declared again here".to_string()),
+        }
+    }
+
+    fn help(&self) -> Option<String> {
+        match self {
+            ParseFault::TrailingToken { .. } => Some("statements are separated by `;`".to_string()),
+            _ => None,
         }
     }
 }
 
-fn check_next_token(tokens: &mut VecDeque<Token>, assert: Token) -> Result<(), ParseFault> {
-    let Some(tkn) = tokens.pop_front() else {
-        return Err(ParseFault::Syntax);
-    };
-    if tkn != assert {
-        return Err(ParseFault::Syntax);
+const EXPECTED_EXPRESSION: &str = "an expression";
+const EXPECTED_IDENTIFIER: &str = "an identifier";
+
+// 1文のトークン列
+// トークンが足りない場合は最後のトークンの直後をエラーの位置にする
+struct Tokens {
+    tokens: VecDeque<SpannedToken>,
+    end: Position,
+    consumed_end: Position,
+}
+
+impl Tokens {
+    fn new(tokens: VecDeque<SpannedToken>) -> Self {
+        let end = tokens.back().map(|t| t.span.end).unwrap_or_default();
+        let consumed_end = tokens.front().map(|t| t.span.start).unwrap_or_default();
+        Tokens {
+            tokens,
+            end,
+            consumed_end,
+        }
+    }
+
+    // トークンがない場合はexpectedを使ってエラーにする
+    fn pop_front(&mut self, expected: &str) -> Result<SpannedToken, Spanned<ParseFault>> {
+        let Some(token) = self.tokens.pop_front() else {
+            return Err(Spanned::new(
+                ParseFault::UnexpectedEnd {
+                    expected: expected.to_string(),
+                },
+                Span::at(self.end),
+            ));
+        };
+        self.consumed_end = token.span.end;
+        Ok(token)
+    }
+
+    fn front(&self) -> Option<&SpannedToken> {
+        self.tokens.front()
+    }
+
+    // 先頭がドキュメントコメントの場合だけ取り出す
+    fn pop_doc_comment(&mut self) -> Option<String> {
+        if !matches!(self.tokens.front()?.token, Token::DocComment(_)) {
+            return None;
+        }
+        match self.tokens.pop_front()?.token {
+            Token::DocComment(line) => Some(line),
+            _ => None,
+        }
+    }
+
+    // startから最後に読んだトークンまで
+    fn span_from(&self, start: Position) -> Span {
+        Span::new(start, self.consumed_end)
+    }
+}
+
+fn unexpected<T>(found: Token, span: Span, expected: &str) -> Result<T, Spanned<ParseFault>> {
+    Err(Spanned::new(
+        ParseFault::Unexpected {
+            expected: expected.to_string(),
+            found,
+        },
+        span,
+    ))
+}
+
+fn check_next_token(tokens: &mut Tokens, assert: Token) -> Result<(), Spanned<ParseFault>> {
+    let expected = assert.to_string();
+    let tkn = tokens.pop_front(&expected)?;
+    if tkn.token != assert {
+        return unexpected(tkn.token, tkn.span, &expected);
     };
     Ok(())
 }
 
-fn peek_token_is(tokens: &mut VecDeque<Token>, assert: Token) -> bool {
+fn peek_token_is(tokens: &Tokens, assert: Token) -> bool {
     let Some(tkn) = tokens.front() else {
         return false;
     };
 
-    tkn == &assert
+    tkn.token == assert
 }
 
-fn parse_function(name: String, tokens: &mut VecDeque<Token>) -> Result<Call, ParseFault> {
+fn parse_function(name: String, tokens: &mut Tokens) -> Result<Call, Spanned<ParseFault>> {
     check_next_token(tokens, Token::LeftPare)?;
     if peek_token_is(tokens, Token::RightPare) {
+        check_next_token(tokens, Token::RightPare)?;
         return Ok(Call {
             name,
             args: Vec::new(),
@@ -82,7 +371,11 @@ fn parse_function(name: String, tokens: &mut VecDeque<Token>) -> Result<Call, Pa
     args.push(parse_expression(tokens)?);
 
     while !peek_token_is(tokens, Token::RightPare) {
-        check_next_token(tokens, Token::Comma)?;
+        let expected = "`,` or `)`";
+        let tkn = tokens.pop_front(expected)?;
+        if tkn.token != Token::Comma {
+            return unexpected(tkn.token, tkn.span, expected);
+        }
         args.push(parse_expression(tokens)?);
     }
 
@@ -91,46 +384,94 @@ fn parse_function(name: String, tokens: &mut VecDeque<Token>) -> Result<Call, Pa
     Ok(Call { name, args })
 }
 
-fn parse_expression(tokens: &mut VecDeque<Token>) -> Result<Expression, ParseFault> {
-    let Some(front_token) = tokens.pop_front() else {
-        return Err(ParseFault::Syntax);
-    };
+fn parse_expression(tokens: &mut Tokens) -> Result<Expression, Spanned<ParseFault>> {
+    parse_expression_bp(tokens, 0)
+}
+
+// Pratt parser
+// min_bpより弱く結合する演算子が来たら止まる
+fn parse_expression_bp(tokens: &mut Tokens, min_bp: u8) -> Result<Expression, Spanned<ParseFault>> {
+    let start = tokens.front().map(|t| t.span.start).unwrap_or_default();
+    let mut left = parse_primary(tokens)?;
+
+    while let Some(op) = tokens.front().and_then(|t| BinaryOp::from_token(&t.token)) {
+        let (left_bp, right_bp) = op.binding_power();
+        if left_bp < min_bp {
+            break;
+        }
+        tokens.pop_front("an operator")?;
+
+        let right = parse_expression_bp(tokens, right_bp)?;
+        left = Expression {
+            kind: ExpressionKind::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            },
+            span: tokens.span_from(start),
+        };
+    }
 
-    let exp = match front_token {
-        Token::HexColor(color) => Ok(Expression::Color(color)),
+    Ok(left)
+}
+
+fn parse_primary(tokens: &mut Tokens) -> Result<Expression, Spanned<ParseFault>> {
+    let front_token = tokens.pop_front(EXPECTED_EXPRESSION)?;
+    let start = front_token.span.start;
+
+    let kind = match front_token.token {
+        Token::HexColor(color) => ExpressionKind::Color(color),
         Token::Identifier(name) => {
             if peek_token_is(tokens, Token::LeftPare) {
-                Ok(Expression::Call(parse_function(name, tokens)?))
+                ExpressionKind::Call(parse_function(name, tokens)?)
             } else {
-                Ok(Expression::Identifier(name))
+                ExpressionKind::Identifier(name)
+            }
+        }
+        Token::Number(number) => ExpressionKind::Number(number),
+        Token::Percent(number) => ExpressionKind::Percent(number),
+        Token::Str(text) => ExpressionKind::Str(text),
+        Token::LeftPare => {
+            let inner = parse_expression(tokens)?;
+            check_next_token(tokens, Token::RightPare)?;
+            inner.kind
+        }
+        Token::Minus => {
+            let operand = parse_expression_bp(tokens, PREFIX_BINDING_POWER)?;
+            match operand.kind {
+                // `-10` は負の数のリテラルとして扱う
+                ExpressionKind::Number(number) => ExpressionKind::Number(-number),
+                ExpressionKind::Percent(number) => ExpressionKind::Percent(-number),
+                _ => ExpressionKind::Negate(Box::new(operand)),
             }
         }
-        Token::Int(int) => Ok(Expression::Int(int)),
-        _ => Err(ParseFault::Syntax),
-    }?;
+        token => return unexpected(token, front_token.span, EXPECTED_EXPRESSION),
+    };
 
-    Ok(exp)
+    Ok(Expression {
+        kind,
+        span: tokens.span_from(start),
+    })
 }
 
-fn parse_let_statement(tokens: &mut VecDeque<Token>) -> Result<LetStatement, ParseFault> {
-    let Some(iden_token) = tokens.pop_front() else {
-        return Err(ParseFault::Syntax);
-    };
+fn parse_let_statement(tokens: &mut Tokens) -> Result<LetStatement, Spanned<ParseFault>> {
+    let iden_token = tokens.pop_front(EXPECTED_IDENTIFIER)?;
 
-    let identifier = match iden_token {
+    let identifier = match iden_token.token {
         Token::Identifier(id) => id,
         _ => {
-            return Err(ParseFault::Syntax);
+            return unexpected(iden_token.token, iden_token.span, EXPECTED_IDENTIFIER);
         }
     };
 
-    let Some(assgin_token) = tokens.pop_front() else {
-        return Err(ParseFault::Syntax);
-    };
+    parse_short_let_statement(identifier, tokens)
+}
 
-    if assgin_token != Token::Assign {
-        return Err(ParseFault::Syntax);
-    };
+fn parse_short_let_statement(
+    identifier: String,
+    tokens: &mut Tokens,
+) -> Result<LetStatement, Spanned<ParseFault>> {
+    check_next_token(tokens, Token::Assign)?;
 
     let exp = parse_expression(tokens)?;
 
@@ -140,73 +481,224 @@ fn parse_let_statement(tokens: &mut VecDeque<Token>) -> Result<LetStatement, Par
     })
 }
 
-fn parse_short_let_statement(
-    identifier: String,
-    tokens: &mut VecDeque<Token>,
-) -> Result<LetStatement, ParseFault> {
-    let Some(assgin_token) = tokens.pop_front() else {
-        return Err(ParseFault::Syntax);
+fn parse_highlight_statement(
+    tokens: &mut Tokens,
+) -> Result<HighlightStatement, Spanned<ParseFault>> {
+    let expected = "a highlight group and attribute like `Normal.fg`";
+    let target_token = tokens.pop_front(expected)?;
+    let target = match &target_token.token {
+        Token::Identifier(name) => split_highlight_target(name),
+        _ => None,
     };
+    let Some((group, attr)) = target else {
+        return unexpected(target_token.token, target_token.span, expected);
+    };
+
+    check_next_token(tokens, Token::Assign)?;
+    let value = parse_expression(tokens)?;
+    Ok(HighlightStatement { group, attr, value })
+}
 
-    if assgin_token != Token::Assign {
-        return Err(ParseFault::Syntax);
+fn parse_function_statement(tokens: &mut Tokens) -> Result<FunctionStatement, Spanned<ParseFault>> {
+    let name_token = tokens.pop_front(EXPECTED_IDENTIFIER)?;
+    let Token::Identifier(name) = name_token.token else {
+        return unexpected(name_token.token, name_token.span, EXPECTED_IDENTIFIER);
     };
 
-    let exp = parse_expression(tokens)?;
+    check_next_token(tokens, Token::LeftPare)?;
+    let mut params = Vec::new();
+    if peek_token_is(tokens, Token::RightPare) {
+        check_next_token(tokens, Token::RightPare)?;
+    } else {
+        loop {
+            let param_token = tokens.pop_front(EXPECTED_IDENTIFIER)?;
+            let Token::Identifier(param) = param_token.token else {
+                return unexpected(param_token.token, param_token.span, EXPECTED_IDENTIFIER);
+            };
+            if params.contains(&param) {
+                return Err(Spanned::new(
+                    ParseFault::DuplicateParameter { name: param },
+                    param_token.span,
+                ));
+            }
+            params.push(param);
 
-    Ok(LetStatement {
-        left: identifier,
-        right: exp,
-    })
+            let expected = "`,` or `)`";
+            let tkn = tokens.pop_front(expected)?;
+            match tkn.token {
+                Token::Comma => continue,
+                Token::RightPare => break,
+                token => return unexpected(token, tkn.span, expected),
+            }
+        }
+    }
+
+    check_next_token(tokens, Token::Assign)?;
+    let body = parse_expression(tokens)?;
+
+    Ok(FunctionStatement { name, params, body })
 }
 
-fn parse_include_statement(tokens: &mut VecDeque<Token>) -> Result<IncludeStatement, ParseFault> {
-    let Some(path_token) = tokens.pop_front() else {
-        return Err(ParseFault::Syntax);
-    };
+fn parse_include_statement(tokens: &mut Tokens) -> Result<IncludeStatement, Spanned<ParseFault>> {
+    let expected = "a file path";
+    let path_token = tokens.pop_front(expected)?;
 
-    let path = match path_token {
-        Token::Identifier(str) => str,
+    // `include "a b.dfr"` と `include a.dfr` の両方を受け付ける
+    let path = match path_token.token {
+        Token::Identifier(str) | Token::Str(str) => str,
         _ => {
-            return Err(ParseFault::Syntax);
+            return unexpected(path_token.token, path_token.span, expected);
         }
     };
 
     Ok(IncludeStatement { path })
 }
 
-pub fn parse_tokens_to_statement(
-    mut line_tokens: VecDeque<Token>,
-) -> Result<Statement, ParseFault> {
-    let Some(front_token) = line_tokens.pop_front() else {
-        return Err(ParseFault::Syntax);
+fn parse_palette_statement(tokens: &mut Tokens) -> Result<PaletteStatement, Spanned<ParseFault>> {
+    let let_stmt = parse_let_statement(tokens)?;
+
+    Ok(PaletteStatement {
+        name: let_stmt.left,
+        right: let_stmt.right,
+    })
+}
+
+fn parse_assert_statement(tokens: &mut Tokens) -> Result<AssertStatement, Spanned<ParseFault>> {
+    let left = parse_expression(tokens)?;
+
+    let expected = "a comparison operator";
+    let op_token = tokens.pop_front(expected)?;
+    let Some(op) = CompareOp::from_token(&op_token.token) else {
+        return unexpected(op_token.token, op_token.span, expected);
     };
 
-    let stmt = match front_token {
-        Token::Let => Statement::Let(parse_let_statement(&mut line_tokens)?),
+    let right = parse_expression(tokens)?;
+
+    Ok(AssertStatement { left, op, right })
+}
+
+pub fn parse_tokens_to_statement(
+    line_tokens: VecDeque<SpannedToken>,
+) -> Result<Statement, Spanned<ParseFault>> {
+    let mut line_tokens = Tokens::new(line_tokens);
+
+    let mut doc_lines = Vec::new();
+    while let Some(line) = line_tokens.pop_doc_comment() {
+        doc_lines.push(line);
+    }
+
+    let expected = "a statement";
+    let front_token = line_tokens.pop_front(expected)?;
+    let start = front_token.span.start;
+
+    let kind = match front_token.token {
+        Token::Let => StatementKind::Let(parse_let_statement(&mut line_tokens)?),
         Token::Identifier(identifier) => {
-            Statement::Let(parse_short_let_statement(identifier, &mut line_tokens)?)
+            StatementKind::Let(parse_short_let_statement(identifier, &mut line_tokens)?)
         }
-        Token::Include => Statement::Include(parse_include_statement(&mut line_tokens)?),
-        _ => {
-            return Err(ParseFault::Syntax);
+        Token::Include => StatementKind::Include(parse_include_statement(&mut line_tokens)?),
+        Token::Assert => StatementKind::Assert(parse_assert_statement(&mut line_tokens)?),
+        Token::Palette => StatementKind::Palette(parse_palette_statement(&mut line_tokens)?),
+        Token::Fn => StatementKind::Function(parse_function_statement(&mut line_tokens)?),
+        Token::Highlight => StatementKind::Highlight(parse_highlight_statement(&mut line_tokens)?),
+        token => {
+            return unexpected(token, front_token.span, expected);
         }
     };
 
-    if line_tokens.len() != 0 {
-        return Err(ParseFault::Syntax);
+    if let Some(extra_token) = line_tokens.tokens.pop_front() {
+        return Err(Spanned::new(
+            ParseFault::TrailingToken {
+                found: extra_token.token,
+            },
+            extra_token.span,
+        ));
     };
 
-    Ok(stmt)
+    // ドキュメントは変数を定義する文にだけつける
+    let doc = match kind {
+        StatementKind::Let(_) | StatementKind::Palette(_) if !doc_lines.is_empty() => {
+            Some(doc_lines.join("\n"))
+        }
+        _ => None,
+    };
+
+    Ok(Statement {
+        kind,
+        span: line_tokens.span_from(start),
+        doc,
+    })
+}
+
+// 1つの文を読むときに起きるエラー
+#[derive(Debug)]
+pub enum StatementFault {
+    Lex(Spanned<LexFault>),
+    Parse(Spanned<ParseFault>),
+}
+
+// ファイル全体のトークン列から`;`で区切られた文を順番に取り出す
+// 読めないトークンを含む文は、その文だけを飛ばす
+pub struct Statements {
+    tokens: VecDeque<LexedToken>,
+}
+
+impl Iterator for Statements {
+    type Item = Result<Statement, StatementFault>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.tokens.is_empty() {
+                return None;
+            }
+
+            let mut line_tokens = VecDeque::new();
+            let mut lex_fault = None;
+            while let Some(token) = self.tokens.pop_front() {
+                match token {
+                    Ok(SpannedToken {
+                        token: Token::Semicolon,
+                        ..
+                    }) => break,
+                    Ok(token) => line_tokens.push_back(token),
+                    Err(fault) => {
+                        lex_fault.get_or_insert(fault);
+                    }
+                }
+            }
+
+            if let Some(fault) = lex_fault {
+                return Some(Err(StatementFault::Lex(fault)));
+            }
+
+            // 空の文、またはドキュメントコメントだけの文
+            let is_doc_comment = |t: &SpannedToken| matches!(t.token, Token::DocComment(_));
+            if line_tokens.iter().all(is_doc_comment) {
+                continue;
+            }
+
+            return Some(parse_tokens_to_statement(line_tokens).map_err(StatementFault::Parse));
+        }
+    }
+}
+
+pub fn parse_statements(tokens: VecDeque<LexedToken>) -> Statements {
+    Statements { tokens }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{parse_tokens_to_statement, LetStatement, ParseFault, Statement};
+    use super::{
+        parse_statements, parse_tokens_to_statement, AssertStatement, CompareOp, FunctionStatement,
+        HighlightStatement, LetStatement, PaletteStatement, ParseFault, StatementFault,
+        StatementKind,
+    };
     use crate::{
         color::Color,
-        lexer::lexer,
-        parser::{Call, Expression},
+        highlight::HighlightAttr,
+        lexer::{lexer, Token},
+        location::{Position, Span},
+        parser::{BinaryOp, Call, Expression, ExpressionKind},
     };
 
     #[test]
@@ -214,45 +706,52 @@ mod test {
         test_parse_statement(
             "1",
             "let hello = #ffffff",
-            Statement::Let(LetStatement {
+            StatementKind::Let(LetStatement {
                 left: "hello".to_string(),
-                right: Expression::Color(Color::new(255, 255, 255)),
+                right: Expression::color(Color::new(255, 255, 255)),
             }),
         );
         test_parse_statement(
             "2",
             "let color1 = #ffffff",
-            Statement::Let(LetStatement {
+            StatementKind::Let(LetStatement {
                 left: "color1".to_string(),
-                right: Expression::Color(Color::new(255, 255, 255)),
+                right: Expression::color(Color::new(255, 255, 255)),
             }),
         );
         test_parse_statement(
             "3",
             "let color1 = #abcdef",
-            Statement::Let(LetStatement {
+            StatementKind::Let(LetStatement {
                 left: "color1".to_string(),
-                right: Expression::Color(Color::new(0xab, 0xcd, 0xef)),
+                right: Expression::color(Color::new(0xab, 0xcd, 0xef)),
             }),
         );
         test_parse_statement(
             "4",
             "include /hello/world",
-            Statement::Include(super::IncludeStatement {
+            StatementKind::Include(super::IncludeStatement {
                 path: "/hello/world".to_string(),
             }),
         );
+        test_parse_statement(
+            "4 string",
+            "include \"../my themes/base (v2).dfr\"",
+            StatementKind::Include(super::IncludeStatement {
+                path: "../my themes/base (v2).dfr".to_string(),
+            }),
+        );
         test_parse_statement(
             "5",
             "let hello = rgb(10,20,30)",
-            Statement::Let(LetStatement {
+            StatementKind::Let(LetStatement {
                 left: "hello".to_string(),
-                right: Expression::Call(Call {
+                right: Expression::call(Call {
                     name: "rgb".to_string(),
                     args: vec![
-                        Expression::Int(10),
-                        Expression::Int(20),
-                        Expression::Int(30),
+                        Expression::number(10.0),
+                        Expression::number(20.0),
+                        Expression::number(30.0),
                     ],
                 }),
             }),
@@ -260,23 +759,23 @@ mod test {
         test_parse_statement(
             "6",
             "let hello = plus(rgb(10,10,10),10,20,30)",
-            Statement::Let(LetStatement {
+            StatementKind::Let(LetStatement {
                 left: "hello".to_string(),
 
-                right: Expression::Call(Call {
+                right: Expression::call(Call {
                     name: "plus".to_string(),
                     args: vec![
-                        Expression::Call(Call {
+                        Expression::call(Call {
                             name: "rgb".to_string(),
                             args: vec![
-                                Expression::Int(10),
-                                Expression::Int(10),
-                                Expression::Int(10),
+                                Expression::number(10.0),
+                                Expression::number(10.0),
+                                Expression::number(10.0),
                             ],
                         }),
-                        Expression::Int(10),
-                        Expression::Int(20),
-                        Expression::Int(30),
+                        Expression::number(10.0),
+                        Expression::number(20.0),
+                        Expression::number(30.0),
                     ],
                 }),
             }),
@@ -284,80 +783,216 @@ mod test {
         test_parse_statement(
             "7 shrort let",
             "hello = plus(rgb(10,10,10),10,20,30)",
-            Statement::Let(LetStatement {
+            StatementKind::Let(LetStatement {
                 left: "hello".to_string(),
 
-                right: Expression::Call(Call {
+                right: Expression::call(Call {
                     name: "plus".to_string(),
                     args: vec![
-                        Expression::Call(Call {
+                        Expression::call(Call {
                             name: "rgb".to_string(),
                             args: vec![
-                                Expression::Int(10),
-                                Expression::Int(10),
-                                Expression::Int(10),
+                                Expression::number(10.0),
+                                Expression::number(10.0),
+                                Expression::number(10.0),
                             ],
                         }),
-                        Expression::Int(10),
-                        Expression::Int(20),
-                        Expression::Int(30),
+                        Expression::number(10.0),
+                        Expression::number(20.0),
+                        Expression::number(30.0),
+                    ],
+                }),
+            }),
+        );
+    }
+
+    #[test]
+    fn test_parse_assert_statement() {
+        test_parse_statement(
+            "1",
+            "assert contrast(fg, bg) >= 4.5",
+            StatementKind::Assert(AssertStatement {
+                left: Expression::call(Call {
+                    name: "contrast".to_string(),
+                    args: vec![
+                        Expression::identifier("fg".to_string()),
+                        Expression::identifier("bg".to_string()),
+                    ],
+                }),
+                op: CompareOp::GreaterEqual,
+                right: Expression::number(4.5),
+            }),
+        );
+        test_parse_statement(
+            "2",
+            "assert 1 < luminance(bg)",
+            StatementKind::Assert(AssertStatement {
+                left: Expression::number(1.0),
+                op: CompareOp::Less,
+                right: Expression::call(Call {
+                    name: "luminance".to_string(),
+                    args: vec![Expression::identifier("bg".to_string())],
+                }),
+            }),
+        );
+    }
+
+    #[test]
+    fn test_parse_palette_statement() {
+        test_parse_statement(
+            "1",
+            "palette blue = ramp(#3366cc, 10)",
+            StatementKind::Palette(PaletteStatement {
+                name: "blue".to_string(),
+                right: Expression::call(Call {
+                    name: "ramp".to_string(),
+                    args: vec![
+                        Expression::color(Color::new(0x33, 0x66, 0xcc)),
+                        Expression::number(10.0),
                     ],
                 }),
             }),
         );
+        test_parse_statement_err("2", "palette blue", end("`=`"));
+        test_parse_statement_err(
+            "3",
+            "palette = ramp(a, 10)",
+            unexpected("an identifier", Token::Assign),
+        );
     }
 
     #[test]
     fn parse_tokens_to_statement_err() {
-        test_parse_statement_err("1", "hello", ParseFault::Syntax);
-        test_parse_statement_err("2", "let hello hello = hello", ParseFault::Syntax);
-        test_parse_statement_err("3", "let hello = hello( 10 ", ParseFault::Syntax);
-        test_parse_statement_err("4", "let hello = hello( 10 ", ParseFault::Syntax);
-        test_parse_statement_err("5", " = cargo ", ParseFault::Syntax);
-        test_parse_statement_err("6", "include 1", ParseFault::Syntax);
-        test_parse_statement_err("7", "include hello hello", ParseFault::Syntax);
+        test_parse_statement_err("1", "hello", end("`=`"));
+        test_parse_statement_err(
+            "2",
+            "let hello hello = hello",
+            unexpected("`=`", Token::Identifier("hello".to_string())),
+        );
+        test_parse_statement_err("3", "let hello = hello( 10 ", end("`,` or `)`"));
+        test_parse_statement_err(
+            "4",
+            "let hello = hello( 10 20 )",
+            unexpected("`,` or `)`", Token::Number(20.0)),
+        );
+        test_parse_statement_err("5", " = cargo ", unexpected("a statement", Token::Assign));
+        test_parse_statement_err(
+            "6",
+            "include 1",
+            unexpected("a file path", Token::Number(1.0)),
+        );
+        test_parse_statement_err(
+            "7",
+            "include hello hello",
+            ParseFault::TrailingToken {
+                found: Token::Identifier("hello".to_string()),
+            },
+        );
+        test_parse_statement_err("8", "assert a", end("a comparison operator"));
+        test_parse_statement_err(
+            "9",
+            "assert a = b",
+            unexpected("a comparison operator", Token::Assign),
+        );
+        test_parse_statement_err("10", "assert a >= ", end("an expression"));
+        test_parse_statement_err(
+            "11",
+            "assert a >= b c",
+            ParseFault::TrailingToken {
+                found: Token::Identifier("c".to_string()),
+            },
+        );
+        test_parse_statement_err("12", "let a = rgb(1, 2, 3", end("`,` or `)`"));
+        test_parse_statement_err(
+            "13",
+            "let a = )",
+            unexpected("an expression", Token::RightPare),
+        );
+    }
+
+    fn end(expected: &str) -> ParseFault {
+        ParseFault::UnexpectedEnd {
+            expected: expected.to_string(),
+        }
+    }
+
+    fn unexpected(expected: &str, found: Token) -> ParseFault {
+        ParseFault::Unexpected {
+            expected: expected.to_string(),
+            found,
+        }
     }
 
     fn test_parse_statement_err(test_name: &str, stmt_str: &str, assert: ParseFault) {
         println!("test {}", test_name);
         let mut chars = stmt_str.chars().collect();
-        let tokens = lexer(&mut chars).unwrap();
+        let tokens = lexer(&mut chars).into_iter().map(|t| t.unwrap()).collect();
         let parsed = parse_tokens_to_statement(tokens).unwrap_err();
-        assert_eq!(parsed, assert);
+        assert_eq!(parsed.inner, assert);
     }
 
     fn assert_function(test_name: &str, a: Call, b: Call) {
         assert_eq!(a.name, b.name, "{}", test_name);
         assert_eq!(a.args.len(), b.args.len(), "{}", test_name);
-        for (a_i, b_i) in a.args.into_iter().zip(b.args.into_iter()) {
+        for (a_i, b_i) in a.args.into_iter().zip(b.args) {
             assert_expression(test_name, a_i, b_i)
         }
     }
 
     fn assert_expression(test_name: &str, a: Expression, b: Expression) {
-        match a {
-            Expression::Color(a_val) => match b {
-                Expression::Color(b_val) => {
+        match a.kind {
+            ExpressionKind::Color(a_val) => match b.kind {
+                ExpressionKind::Color(b_val) => {
                     assert_eq!(a_val, b_val, "{}", test_name)
                 }
                 _ => panic!("{}", test_name),
             },
-            Expression::Call(a_val) => match b {
-                Expression::Call(b_val) => assert_function(test_name, a_val, b_val),
+            ExpressionKind::Call(a_val) => match b.kind {
+                ExpressionKind::Call(b_val) => assert_function(test_name, a_val, b_val),
                 _ => panic!("{}", test_name),
             },
-            Expression::Int(a_val) => match b {
-                Expression::Int(b_val) => {
+            ExpressionKind::Number(a_val) => match b.kind {
+                ExpressionKind::Number(b_val) => {
                     assert_eq!(a_val, b_val, "{}", test_name);
                 }
                 _ => panic!("{}", test_name),
             },
-            Expression::Identifier(a_val) => match b {
-                Expression::Identifier(b_val) => {
+            ExpressionKind::Percent(a_val) => match b.kind {
+                ExpressionKind::Percent(b_val) => {
+                    assert_eq!(a_val, b_val, "{}", test_name);
+                }
+                _ => panic!("{}", test_name),
+            },
+            ExpressionKind::Str(a_val) => match b.kind {
+                ExpressionKind::Str(b_val) => assert_eq!(a_val, b_val, "{}", test_name),
+                _ => panic!("{}", test_name),
+            },
+            ExpressionKind::Identifier(a_val) => match b.kind {
+                ExpressionKind::Identifier(b_val) => {
                     assert_eq!(a_val, b_val, "{}", test_name)
                 }
                 _ => panic!("{}", test_name),
             },
+            ExpressionKind::Binary {
+                op: a_op,
+                left: a_left,
+                right: a_right,
+            } => match b.kind {
+                ExpressionKind::Binary {
+                    op: b_op,
+                    left: b_left,
+                    right: b_right,
+                } => {
+                    assert_eq!(a_op, b_op, "{}", test_name);
+                    assert_expression(test_name, *a_left, *b_left);
+                    assert_expression(test_name, *a_right, *b_right);
+                }
+                _ => panic!("{}", test_name),
+            },
+            ExpressionKind::Negate(a_val) => match b.kind {
+                ExpressionKind::Negate(b_val) => assert_expression(test_name, *a_val, *b_val),
+                _ => panic!("{}", test_name),
+            },
         }
     }
 
@@ -366,23 +1001,286 @@ mod test {
         assert_expression(test_name, a.right, b.right);
     }
 
-    fn test_parse_statement(test_name: &str, stmt_str: &str, assert_stmt: Statement) {
+    fn test_parse_statement(test_name: &str, stmt_str: &str, assert_stmt: StatementKind) {
         println!("test {}", test_name);
         let mut chars = stmt_str.chars().collect();
-        let tokens = lexer(&mut chars).unwrap();
+        let tokens = lexer(&mut chars).into_iter().map(|t| t.unwrap()).collect();
         let parsed = parse_tokens_to_statement(tokens).unwrap();
 
-        match parsed {
-            Statement::Include(include_stmt) => match assert_stmt {
-                Statement::Include(a_include_stmt) => {
+        match parsed.kind {
+            StatementKind::Include(include_stmt) => match assert_stmt {
+                StatementKind::Include(a_include_stmt) => {
                     assert_eq!(include_stmt.path, a_include_stmt.path, "{}", test_name)
                 }
                 _ => panic!("{}", test_name),
             },
-            Statement::Let(let_stmt) => match assert_stmt {
-                Statement::Let(a_let_stmt) => assert_let_stmt(test_name, let_stmt, a_let_stmt),
+            StatementKind::Let(let_stmt) => match assert_stmt {
+                StatementKind::Let(a_let_stmt) => assert_let_stmt(test_name, let_stmt, a_let_stmt),
+                _ => panic!("{}", test_name),
+            },
+            StatementKind::Palette(palette_stmt) => match assert_stmt {
+                StatementKind::Palette(a_palette_stmt) => {
+                    assert_eq!(palette_stmt.name, a_palette_stmt.name, "{}", test_name);
+                    assert_expression(test_name, palette_stmt.right, a_palette_stmt.right);
+                }
+                _ => panic!("{}", test_name),
+            },
+            StatementKind::Assert(assert_stmt_) => match assert_stmt {
+                StatementKind::Assert(a_assert_stmt) => {
+                    assert_eq!(assert_stmt_.op, a_assert_stmt.op, "{}", test_name);
+                    assert_expression(test_name, assert_stmt_.left, a_assert_stmt.left);
+                    assert_expression(test_name, assert_stmt_.right, a_assert_stmt.right);
+                }
+                _ => panic!("{}", test_name),
+            },
+            StatementKind::Highlight(highlight_stmt) => match assert_stmt {
+                StatementKind::Highlight(a_highlight_stmt) => {
+                    assert_eq!(
+                        highlight_stmt.group, a_highlight_stmt.group,
+                        "{}",
+                        test_name
+                    );
+                    assert_eq!(highlight_stmt.attr, a_highlight_stmt.attr, "{}", test_name);
+                    assert_expression(test_name, highlight_stmt.value, a_highlight_stmt.value);
+                }
+                _ => panic!("{}", test_name),
+            },
+            StatementKind::Function(function_stmt) => match assert_stmt {
+                StatementKind::Function(a_function_stmt) => {
+                    assert_eq!(function_stmt.name, a_function_stmt.name, "{}", test_name);
+                    assert_eq!(
+                        function_stmt.params, a_function_stmt.params,
+                        "{}",
+                        test_name
+                    );
+                    assert_expression(test_name, function_stmt.body, a_function_stmt.body);
+                }
                 _ => panic!("{}", test_name),
             },
         }
     }
+
+    #[test]
+    fn operator() {
+        let let_stmt = |right: Expression| {
+            StatementKind::Let(LetStatement {
+                left: "a".to_string(),
+                right,
+            })
+        };
+        let ident = |name: &str| Expression::identifier(name.to_string());
+
+        test_parse_statement(
+            "precedence",
+            "let a = b + c * 2",
+            let_stmt(Expression::binary(
+                BinaryOp::Add,
+                ident("b"),
+                Expression::binary(BinaryOp::Mul, ident("c"), Expression::number(2.0)),
+            )),
+        );
+        test_parse_statement(
+            "left associative",
+            "let a = b - c - d",
+            let_stmt(Expression::binary(
+                BinaryOp::Sub,
+                Expression::binary(BinaryOp::Sub, ident("b"), ident("c")),
+                ident("d"),
+            )),
+        );
+        test_parse_statement(
+            "parentheses",
+            "let a = (b + c) / 2",
+            let_stmt(Expression::binary(
+                BinaryOp::Div,
+                Expression::binary(BinaryOp::Add, ident("b"), ident("c")),
+                Expression::number(2.0),
+            )),
+        );
+        test_parse_statement(
+            "negate",
+            "let a = -b * -2",
+            let_stmt(Expression::binary(
+                BinaryOp::Mul,
+                Expression::negate(ident("b")),
+                Expression::number(-2.0),
+            )),
+        );
+        test_parse_statement(
+            "in arguments",
+            "let a = f(b * 0.5, -1)",
+            let_stmt(Expression::call(Call {
+                name: "f".to_string(),
+                args: vec![
+                    Expression::binary(BinaryOp::Mul, ident("b"), Expression::number(0.5)),
+                    Expression::number(-1.0),
+                ],
+            })),
+        );
+
+        test_parse_statement(
+            "percent",
+            "let a = b * -50%",
+            let_stmt(Expression::binary(
+                BinaryOp::Mul,
+                ident("b"),
+                Expression::percent(-50.0),
+            )),
+        );
+
+        test_parse_statement_err("missing operand", "let a = b *", end("an expression"));
+        test_parse_statement_err("unclosed", "let a = (b + c", end("`)`"));
+    }
+
+    #[test]
+    fn function_statement() {
+        test_parse_statement(
+            "params",
+            "fn hover(c, amount) = lighten(c, amount)",
+            StatementKind::Function(FunctionStatement {
+                name: "hover".to_string(),
+                params: vec!["c".to_string(), "amount".to_string()],
+                body: Expression::call(Call {
+                    name: "lighten".to_string(),
+                    args: vec![
+                        Expression::identifier("c".to_string()),
+                        Expression::identifier("amount".to_string()),
+                    ],
+                }),
+            }),
+        );
+        test_parse_statement(
+            "no params",
+            "fn accent() = #ff8800",
+            StatementKind::Function(FunctionStatement {
+                name: "accent".to_string(),
+                params: vec![],
+                body: Expression::color(Color::new(0xff, 0x88, 0x00)),
+            }),
+        );
+
+        test_parse_statement_err(
+            "duplicate",
+            "fn f(a, a) = a",
+            ParseFault::DuplicateParameter {
+                name: "a".to_string(),
+            },
+        );
+        test_parse_statement_err(
+            "param",
+            "fn f(1) = a",
+            unexpected("an identifier", Token::Number(1.0)),
+        );
+        test_parse_statement_err("body", "fn f(a)", end("`=`"));
+    }
+
+    #[test]
+    fn highlight_statement() {
+        test_parse_statement(
+            "fg",
+            "highlight Normal.fg = fg",
+            StatementKind::Highlight(HighlightStatement {
+                group: "Normal".to_string(),
+                attr: HighlightAttr::Fg,
+                value: Expression::identifier("fg".to_string()),
+            }),
+        );
+        test_parse_statement(
+            "tree-sitter",
+            "highlight @comment.doc.style = \"italic\"",
+            StatementKind::Highlight(HighlightStatement {
+                group: "@comment.doc".to_string(),
+                attr: HighlightAttr::Style,
+                value: Expression::string("italic"),
+            }),
+        );
+        // `highlight`がなければ今まで通り変数になる
+        test_parse_statement(
+            "variable",
+            "Normal.fg = fg",
+            StatementKind::Let(LetStatement {
+                left: "Normal.fg".to_string(),
+                right: Expression::identifier("fg".to_string()),
+            }),
+        );
+        test_parse_statement_err(
+            "attribute",
+            "highlight Normal.color = fg",
+            unexpected(
+                "a highlight group and attribute like `Normal.fg`",
+                Token::Identifier("Normal.color".to_string()),
+            ),
+        );
+    }
+
+    #[test]
+    fn statement_span() {
+        let mut chars = "let a = mix(b, c, 50)".chars().collect();
+        let tokens = lexer(&mut chars).into_iter().map(|t| t.unwrap()).collect();
+        let stmt = parse_tokens_to_statement(tokens).unwrap();
+        assert_eq!(
+            stmt.span,
+            Span::new(Position::new(1, 1), Position::new(1, 22))
+        );
+        let StatementKind::Let(let_stmt) = stmt.kind else {
+            panic!();
+        };
+        assert_eq!(
+            let_stmt.right.span,
+            Span::new(Position::new(1, 9), Position::new(1, 22))
+        );
+
+        test_parse_statement_err_at("let a = mix(b c)", Position::new(1, 15));
+        test_parse_statement_err_at("let a = mix(b, c", Position::new(1, 17));
+        test_parse_statement_err_at("assert a >= b c", Position::new(1, 15));
+    }
+
+    fn test_parse_statement_err_at(stmt_str: &str, position: Position) {
+        let mut chars = stmt_str.chars().collect();
+        let tokens = lexer(&mut chars).into_iter().map(|t| t.unwrap()).collect();
+        let parsed = parse_tokens_to_statement(tokens).unwrap_err();
+        assert_eq!(parsed.span.start, position, "{}", stmt_str);
+    }
+
+    #[test]
+    fn test_parse_statements() {
+        let mut chars = "a = #fff;; b = #gg; c = ; // d = e;\nf = g"
+            .chars()
+            .collect();
+        let results: Vec<_> = parse_statements(lexer(&mut chars)).collect();
+        assert_eq!(results.len(), 4);
+        assert!(matches!(&results[0], Ok(stmt) if stmt.span.start == Position::new(1, 1)));
+        assert!(matches!(&results[1], Err(StatementFault::Lex(_))));
+        assert!(matches!(
+            &results[2],
+            Err(StatementFault::Parse(fault)) if fault.inner == end("an expression")
+        ));
+        assert!(matches!(&results[3], Ok(stmt) if stmt.span.start == Position::new(2, 1)));
+    }
+
+    #[test]
+    fn test_doc_comment() {
+        let parse = |stmt_str: &str| {
+            let mut chars = stmt_str.chars().collect();
+            let tokens = lexer(&mut chars).into_iter().map(|t| t.unwrap()).collect();
+            parse_tokens_to_statement(tokens).unwrap()
+        };
+
+        let stmt = parse("/// Main background\n/// used for panels\nlet bg = #101010");
+        assert_eq!(
+            stmt.doc,
+            Some("Main background\nused for panels".to_string())
+        );
+        assert_eq!(stmt.span.start, Position::new(3, 1));
+
+        let stmt = parse("/// Blues\npalette blue = #3366cc");
+        assert_eq!(stmt.doc, Some("Blues".to_string()));
+
+        let stmt = parse("/// ignored\nassert 1 < 2");
+        assert_eq!(stmt.doc, None);
+
+        // 式の途中の`///`は普通のコメント
+        let stmt = parse("let a = rgb(\n/// x\n1, 2, 3)");
+        assert_eq!(stmt.doc, None);
+    }
 }
@@ -1,61 +1,57 @@
 use std::collections::VecDeque;
 
 use crate::{
-    app_path::AbsFilePath, envroiment::Envroiment, eval::eval, lexer::lexer,
-    parser::parse_tokens_to_statement, utils::peek_take_while,
+    app_path::AbsFilePath,
+    envroiment::{Envroiment, IncludeFileStackFault},
+    eval::eval,
+    lexer::lexer,
+    parser::{parse_statements, StatementFault},
 };
 
-pub fn run(env: &mut Envroiment, mut code_chars: VecDeque<char>, file_path: AbsFilePath) {
-    let result = env.include_file_stack.push(file_path);
-    if let Err(err) = result {
-        env.faults.push(Box::new(err));
-        return;
-    }
-
-    loop {
-        if code_chars.front().is_none() {
-            break;
-        }
-
-        let line = peek_take_while(&mut code_chars, |&ch| ch == ';');
-        code_chars.pop_front();
-
-        let line_string: String = line.into_iter().collect();
-        let mut chars = line_string.chars().collect();
-        let tokens = match lexer(&mut chars) {
-            Ok(tokens) => tokens,
-            Err(err) => {
-                env.faults.push(Box::new(err));
+// 同じファイルを再帰的にincludeしている場合はErrを返す
+pub fn run(
+    env: &mut Envroiment,
+    mut code_chars: VecDeque<char>,
+    file_path: AbsFilePath,
+) -> Result<(), IncludeFileStackFault> {
+    env.include_file_stack.push(file_path)?;
+    env.add_current_source(code_chars.iter().collect());
+
+    for stmt in parse_statements(lexer(&mut code_chars)) {
+        let stmt = match stmt {
+            Ok(stmt) => stmt,
+            Err(StatementFault::Lex(err)) => {
+                env.push_fault(err);
                 continue;
             }
-        };
-
-        if tokens.len() == 0 {
-            continue;
-        }
-
-        let line_stmt = match parse_tokens_to_statement(tokens) {
-            Ok(stmt) => stmt,
-            Err(err) => {
-                env.faults.push(Box::new(err));
+            Err(StatementFault::Parse(err)) => {
+                env.push_fault(err);
                 continue;
             }
         };
 
-        match eval(line_stmt, env) {
-            Err(runtime_fault) => env.faults.push(Box::new(runtime_fault)),
-            _ => (),
-        };
+        if let Err(runtime_fault) = eval(stmt, env) {
+            env.push_fault(runtime_fault);
+        }
     }
 
     env.include_file_stack.pop();
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
 
     use crate::{
-        app_path, color::Color, envroiment::Envroiment, fault, parser::ParseFault, run::run,
+        app_path,
+        color::Color,
+        envroiment::Envroiment,
+        eval::EvalFault,
+        fault,
+        lexer::{LexFault, Token},
+        parser::{BinaryOp, CompareOp, ParseFault},
+        run::run,
+        value::Value,
     };
 
     #[test]
@@ -69,12 +65,16 @@ mod test {
             &mut env,
             code.chars().collect(),
             app_path::AbsFilePath::create_decoy(),
-        );
+        )
+        .unwrap();
         assert_eq!(
             env.get(&"hello".to_string()),
-            Some(Color::new(255, 255, 255))
+            Some(Value::Color(Color::new(255, 255, 255)))
+        );
+        assert_eq!(
+            env.get(&"color2".to_string()),
+            Some(Value::Color(Color::new(20, 30, 40)))
         );
-        assert_eq!(env.get(&"color2".to_string()), Some(Color::new(20, 30, 40)));
 
         let code = "\
         let hello = #ffffff;
@@ -118,17 +118,36 @@ mod test {
             &mut env,
             code.chars().collect(),
             app_path::AbsFilePath::create_decoy(),
-        );
+        )
+        .unwrap();
         assert_eq!(
             env.get(&"hello".to_string()),
-            Some(Color::new(255, 255, 255))
+            Some(Value::Color(Color::new(255, 255, 255)))
+        );
+        assert_eq!(
+            env.get(&"color2".to_string()),
+            Some(Value::Color(Color::new(16, 16, 16)))
+        );
+        assert_eq!(
+            env.get(&"color3".to_string()),
+            Some(Value::Color(Color::new(26, 36, 46)))
+        );
+        assert_eq!(
+            env.get(&"color4".to_string()),
+            Some(Value::Color(Color::new(26, 36, 46)))
+        );
+        assert_eq!(
+            env.get(&"color5".to_string()),
+            Some(Value::Color(Color::new(10, 10, 10)))
+        );
+        assert_eq!(
+            env.get(&"color6".to_string()),
+            Some(Value::Color(Color::new(11, 10, 10)))
+        );
+        assert_eq!(
+            env.get(&"color7".to_string()),
+            Some(Value::Color(Color::new(10, 10, 10)))
         );
-        assert_eq!(env.get(&"color2".to_string()), Some(Color::new(16, 16, 16)));
-        assert_eq!(env.get(&"color3".to_string()), Some(Color::new(26, 36, 46)));
-        assert_eq!(env.get(&"color4".to_string()), Some(Color::new(26, 36, 46)));
-        assert_eq!(env.get(&"color5".to_string()), Some(Color::new(10, 10, 10)));
-        assert_eq!(env.get(&"color6".to_string()), Some(Color::new(11, 10, 10)));
-        assert_eq!(env.get(&"color7".to_string()), Some(Color::new(10, 10, 10)));
 
         let code = "\
         hello = rgb(255,150,0);
@@ -139,11 +158,15 @@ mod test {
             &mut env,
             code.chars().collect(),
             app_path::AbsFilePath::create_decoy(),
+        )
+        .unwrap();
+        assert_eq!(
+            env.get(&"hello".to_string()),
+            Some(Value::Color(Color::new(255, 150, 0)))
         );
-        assert_eq!(env.get(&"hello".to_string()), Some(Color::new(255, 150, 0)));
         assert_eq!(
             env.get(&"hello2".to_string()),
-            Some(Color::new(255, 255, 100))
+            Some(Value::Color(Color::new(255, 255, 100)))
         );
 
         let code = "\
@@ -155,9 +178,16 @@ mod test {
             &mut env,
             code.chars().collect(),
             app_path::AbsFilePath::create_decoy(),
+        )
+        .unwrap();
+        assert_eq!(
+            env.get(&"hello".to_string()),
+            Some(Value::Color(Color::new(0, 0, 0)))
+        );
+        assert_eq!(
+            env.get(&"hello2".to_string()),
+            Some(Value::Color(Color::new(0, 0, 0)))
         );
-        assert_eq!(env.get(&"hello".to_string()), Some(Color::new(0, 0, 0)));
-        assert_eq!(env.get(&"hello2".to_string()), Some(Color::new(0, 0, 0)));
 
         let code = "\
         hello2 = hello
@@ -167,7 +197,8 @@ mod test {
             &mut env,
             code.chars().collect(),
             app_path::AbsFilePath::create_decoy(),
-        );
+        )
+        .unwrap();
         assert_eq!(
             env.faults[0].msg(),
             "EvalError: hello is Not Found".to_string()
@@ -181,7 +212,8 @@ mod test {
             &mut env,
             code.chars().collect(),
             app_path::AbsFilePath::create_decoy(),
-        );
+        )
+        .unwrap();
         assert_eq!(env.faults[0].msg(), "LexError: value error".to_string());
 
         let code = "\
@@ -192,8 +224,12 @@ mod test {
             &mut env,
             code.chars().collect(),
             app_path::AbsFilePath::create_decoy(),
+        )
+        .unwrap();
+        assert_eq!(
+            env.faults[0].msg(),
+            "ParseError: unexpected identifier `hello` after the end of the statement"
         );
-        assert_eq!(env.faults[0].msg(), "ParseError: Syntax".to_string());
 
         let code = "\
         hello  #ffffff;
@@ -203,8 +239,12 @@ mod test {
             &mut env,
             code.chars().collect(),
             app_path::AbsFilePath::create_decoy(),
+        )
+        .unwrap();
+        assert_eq!(
+            env.faults[0].msg(),
+            "ParseError: expected `=`, found color `#ffffff`"
         );
-        assert_eq!(env.faults[0].msg(), "ParseError: Syntax".to_string());
 
         let code = "\
         hello hello2 = #ffffff;
@@ -214,8 +254,12 @@ mod test {
             &mut env,
             code.chars().collect(),
             app_path::AbsFilePath::create_decoy(),
+        )
+        .unwrap();
+        assert_eq!(
+            env.faults[0].msg(),
+            "ParseError: expected `=`, found identifier `hello2`"
         );
-        assert_eq!(env.faults[0].msg(), "ParseError: Syntax".to_string());
 
         test_run_("// let hello = #ffffff", vec![], vec![]);
 
@@ -251,14 +295,23 @@ mod test {
             vec![],
         );
 
-        test_run_("/ hello /", vec![], vec![Box::new(ParseFault::Syntax)]);
+        test_run_(
+            "/ hello /",
+            vec![],
+            vec![Box::new(ParseFault::Unexpected {
+                expected: "a statement".to_string(),
+                found: Token::Slash,
+            })],
+        );
 
         test_run_("/// // let hello", vec![], vec![]);
 
         test_run_(
             "let hello = #ffffff/hello",
             vec![],
-            vec![Box::new(ParseFault::Syntax)],
+            vec![Box::new(EvalFault::NotFound {
+                target_name: "hello".to_string(),
+            })],
         );
 
         test_run_(
@@ -268,6 +321,163 @@ mod test {
         );
 
         test_run_("// // let hello", vec![], vec![]);
+
+        // コメントの中の`;`で文が区切られない
+        test_run_(
+            "\
+            hello = rgb(
+                // hello;hello
+                10,10,10
+            );
+            world = #fff; // ;
+            ",
+            vec![
+                ("hello", Color::new(10, 10, 10)),
+                ("world", Color::new(255, 255, 255)),
+            ],
+            vec![],
+        );
+
+        test_run_(
+            "\
+            shadow = #00000080;
+            short = #fff8;
+            overlay = rgba( 10, 20, 30, 40 );
+            faded = minus( short, 0, 0, 0, 16 );
+            ",
+            vec![
+                ("shadow", Color::new_rgba(0, 0, 0, 0x80)),
+                ("short", Color::new_rgba(0xff, 0xff, 0xff, 0x88)),
+                ("overlay", Color::new_rgba(10, 20, 30, 40)),
+                ("faded", Color::new_rgba(0xff, 0xff, 0xff, 0x78)),
+            ],
+            vec![],
+        );
+
+        test_run_(
+            "\
+            sky = hsl( 210, 50, 40 );
+            sky_a = hsla( 210, 50, 40, 128 );
+            lemon = hsv( 60, 100, 100 );
+            ",
+            vec![
+                ("sky", Color::new(51, 102, 153)),
+                ("sky_a", Color::new_rgba(51, 102, 153, 128)),
+                ("lemon", Color::new(255, 255, 0)),
+            ],
+            vec![],
+        );
+
+        test_run_(
+            "\
+            bg = #ffffff;
+            accent = #3366cc;
+            selection = over( rgba( 51, 102, 204, 128 ), bg );
+            half = mix( accent, bg, 50 );
+            shade = multiply( accent, #808080 );
+            ",
+            vec![
+                ("bg", Color::new(255, 255, 255)),
+                ("accent", Color::new(0x33, 0x66, 0xcc)),
+                ("selection", Color::new(153, 178, 229)),
+                ("half", Color::new(153, 179, 230)),
+                ("shade", Color::new(26, 51, 102)),
+            ],
+            vec![],
+        );
+
+        test_run_(
+            "\
+            red1 = oklab( 0.628, 0.2249, 0.1258 );
+            red2 = oklch( 0.628, 0.2577, 29.23 );
+            red3 = lab( 54.29, 80.8, 69.89 );
+            too_vivid = oklch( 1.5, 0.4, 150 );
+            ",
+            vec![
+                ("red1", Color::new(255, 0, 0)),
+                ("red2", Color::new(255, 0, 0)),
+                ("red3", Color::new(255, 0, 0)),
+                ("too_vivid", Color::new(255, 255, 255)),
+            ],
+            vec![],
+        );
+
+        test_run_(
+            "\
+            fg = #767676;
+            bg = #ffffff;
+            assert contrast( fg, bg ) >= 4.5;
+            assert luminance( bg ) > luminance( fg );
+            assert contrast( fg, bg ) >= 7;
+            assert contrast( fg, bg ) < 1;
+            assert fg >= 1;
+            ",
+            vec![
+                ("fg", Color::new(0x76, 0x76, 0x76)),
+                ("bg", Color::new(0xff, 0xff, 0xff)),
+            ],
+            vec![
+                Box::new(EvalFault::AssertionFailed {
+                    left: 4.54,
+                    op: CompareOp::GreaterEqual,
+                    right: 7.0,
+                }),
+                Box::new(EvalFault::AssertionFailed {
+                    left: 4.54,
+                    op: CompareOp::Less,
+                    right: 1.0,
+                }),
+                Box::new(EvalFault::TypeMismatch {
+                    req: "a number",
+                    got: "color `#767676`".to_string(),
+                }),
+            ],
+        );
+
+        test_run_(
+            "\
+            base = #3366cc;
+            palette blue = ramp( base, 10 );
+            palette red = #cc3333;
+            palette gray = scale( #808080, 0, 1000 );
+            foo = ramp( base, 10 );
+            palette bar = 10;
+            ",
+            vec![
+                ("base", Color::new(0x33, 0x66, 0xcc)),
+                ("blue.50", Color::new(0xdf, 0xea, 0xff)),
+                ("blue.100", Color::new(0xc8, 0xdc, 0xff)),
+                ("blue.200", Color::new(0x9b, 0xbe, 0xff)),
+                ("blue.300", Color::new(0x6e, 0xa0, 0xff)),
+                ("blue.400", Color::new(0x4d, 0x82, 0xeb)),
+                ("blue.500", Color::new(0x33, 0x66, 0xcc)),
+                ("blue.600", Color::new(0x20, 0x52, 0xb6)),
+                ("blue.700", Color::new(0x0c, 0x3d, 0xa0)),
+                ("blue.800", Color::new(0x00, 0x2a, 0x85)),
+                ("blue.900", Color::new(0x00, 0x1d, 0x61)),
+                ("red.50", Color::new(0xff, 0xe3, 0xe0)),
+                ("red.100", Color::new(0xff, 0xd0, 0xca)),
+                ("red.200", Color::new(0xff, 0xa6, 0x9e)),
+                ("red.300", Color::new(0xff, 0x77, 0x6e)),
+                ("red.400", Color::new(0xea, 0x51, 0x4c)),
+                ("red.500", Color::new(0xcc, 0x33, 0x33)),
+                ("red.600", Color::new(0xb3, 0x11, 0x1d)),
+                ("red.700", Color::new(0x92, 0x00, 0x11)),
+                ("red.800", Color::new(0x70, 0x00, 0x0a)),
+                ("red.900", Color::new(0x50, 0x00, 0x05)),
+                ("gray.0", Color::new(0xf8, 0xf8, 0xf8)),
+                ("gray.1000", Color::new(0x16, 0x16, 0x16)),
+            ],
+            vec![
+                Box::new(EvalFault::PaletteAssign {
+                    target_name: "foo".to_string(),
+                }),
+                Box::new(EvalFault::TypeMismatch {
+                    req: "a palette or a color",
+                    got: "number `10`".to_string(),
+                }),
+            ],
+        );
     }
 
     fn test_run_(code: &str, vars: Vec<(&str, Color)>, errs: Vec<Box<dyn fault::Fault>>) {
@@ -277,7 +487,8 @@ mod test {
             &mut env,
             code.chars().collect(),
             app_path::AbsFilePath::create_decoy(),
-        );
+        )
+        .unwrap();
         assert_eq!(env.vars_len(), vars.len());
         assert_eq!(env.faults.len(), errs.len());
 
@@ -286,7 +497,251 @@ mod test {
         }
 
         for var in vars {
-            assert_eq!(env.get(&var.0.to_string()).unwrap(), var.1);
+            assert_eq!(env.get(&var.0.to_string()).unwrap(), Value::Color(var.1));
         }
     }
+
+    #[test]
+    fn arithmetic() {
+        test_run_(
+            "accent = #6496c8; a = accent * 0.8 + #101010; b = (accent - #640000) / 2;",
+            vec![
+                ("accent", Color::new(100, 150, 200)),
+                ("a", Color::new(96, 136, 176)),
+                ("b", Color::new(0, 75, 100)),
+            ],
+            vec![],
+        );
+        test_run_(
+            "a = #ffffff * #ffffff; b = #ffffff / (2 - 2); c = -#ffffff;",
+            vec![],
+            vec![
+                Box::new(EvalFault::InvalidOperands {
+                    op: BinaryOp::Mul,
+                    left: "color",
+                    right: "color",
+                }),
+                Box::new(EvalFault::DivisionByZero),
+                Box::new(EvalFault::InvalidNegation { operand: "color" }),
+            ],
+        );
+    }
+
+    #[test]
+    fn expression_arguments() {
+        test_run_(
+            "\
+            a = #102030;
+            b = rgb( red(a) * 2, 10 + 10, 255 / 2 );
+            c = mix( a, plus(a, 16, 16, 16), 25 * 2 );
+            d = rgb( red(a), a, 0 );
+            ",
+            vec![
+                ("a", Color::new(0x10, 0x20, 0x30)),
+                ("b", Color::new(32, 20, 128)),
+                ("c", Color::new(0x18, 0x28, 0x38)),
+            ],
+            vec![Box::new(EvalFault::ArgType {
+                param: "g",
+                req: "an integer from 0 to 255".to_string(),
+                got: "color `#102030`".to_string(),
+            })],
+        );
+    }
+
+    #[test]
+    fn unknown_function() {
+        test_run_(
+            "\
+            fn accent(c) = lighten(c, 10);
+            a = lighen(#102030, 10);
+            b = acent(#102030);
+            c = foo(#102030);
+            ",
+            vec![],
+            vec![
+                Box::new(EvalFault::IsNotFunction {
+                    target_name: "lighen".to_string(),
+                    suggestion: Some("lighten".to_string()),
+                }),
+                Box::new(EvalFault::IsNotFunction {
+                    target_name: "acent".to_string(),
+                    suggestion: Some("accent".to_string()),
+                }),
+                Box::new(EvalFault::IsNotFunction {
+                    target_name: "foo".to_string(),
+                    suggestion: None,
+                }),
+            ],
+        );
+    }
+
+    #[test]
+    fn number_variable() {
+        let mut env = Envroiment::new();
+        run(
+            &mut env,
+            "step = 10; half = step / 4; amount = 25% * 2; c = plus(#000000, step, step, half);"
+                .chars()
+                .collect(),
+            app_path::AbsFilePath::create_decoy(),
+        )
+        .unwrap();
+        assert_eq!(env.faults.len(), 0);
+        assert_eq!(env.get(&"step".to_string()), Some(Value::Number(10.0)));
+        assert_eq!(env.get(&"half".to_string()), Some(Value::Number(2.5)));
+        assert_eq!(env.get(&"amount".to_string()), Some(Value::Percent(50.0)));
+        assert_eq!(
+            env.get(&"c".to_string()),
+            Some(Value::Color(Color::new(10, 10, 3)))
+        );
+    }
+
+    #[test]
+    fn named_color() {
+        let source = "a = red; tan = #123456; b = tan; c = gray50;";
+
+        let mut env = Envroiment::new();
+        run(
+            &mut env,
+            source.chars().collect(),
+            app_path::AbsFilePath::create_decoy(),
+        )
+        .unwrap();
+        assert_eq!(
+            env.get(&"a".to_string()),
+            Some(Value::Color(Color::new(255, 0, 0)))
+        );
+        assert_eq!(
+            env.get(&"b".to_string()),
+            Some(Value::Color(Color::new(0x12, 0x34, 0x56)))
+        );
+        assert_eq!(env.get(&"c".to_string()), None);
+        assert_eq!(env.faults.len(), 1);
+
+        let mut env = Envroiment::new();
+        env.x11_colors = true;
+        run(
+            &mut env,
+            source.chars().collect(),
+            app_path::AbsFilePath::create_decoy(),
+        )
+        .unwrap();
+        assert_eq!(env.faults.len(), 0);
+        assert_eq!(
+            env.get(&"c".to_string()),
+            Some(Value::Color(Color::new(127, 127, 127)))
+        );
+    }
+
+    #[test]
+    fn user_function() {
+        test_run_(
+            "\
+            fn hover(c) = lighten(c, 10%);
+            fn tint(c, amount) = mix(c, #ffffff, 100 - amount);
+            base = #336699;
+            a = hover(base);
+            b = tint(hover(base), 20);
+            c = hover(base, 1);
+            ",
+            vec![
+                ("base", Color::new(0x33, 0x66, 0x99)),
+                ("a", Color::new(0x40, 0x80, 0xbf)),
+                ("b", Color::new(0x66, 0x99, 0xcc)),
+            ],
+            vec![Box::new(EvalFault::NumArgments { req: 1, got: 2 })],
+        );
+
+        // 引数は呼び出した関数の中でだけ見える
+        test_run_(
+            "\
+            x = #000000;
+            fn inner() = x;
+            fn outer(x) = inner();
+            fn broken(c) = c * #ffffff;
+            a = outer(#ffffff);
+            b = broken(#000000);
+            d = x;
+            ",
+            vec![
+                ("x", Color::new(0, 0, 0)),
+                ("a", Color::new(0, 0, 0)),
+                ("d", Color::new(0, 0, 0)),
+            ],
+            vec![Box::new(EvalFault::InFunction {
+                name: "broken".to_string(),
+                fault: Box::new(EvalFault::InvalidOperands {
+                    op: BinaryOp::Mul,
+                    left: "color",
+                    right: "color",
+                }),
+            })],
+        );
+
+        test_run_(
+            "fn f(c) = g(c); fn g(c) = f(c); a = f(#000000);",
+            vec![],
+            vec![Box::new(EvalFault::RecursionLimit {
+                name: "f".to_string(),
+            })],
+        );
+    }
+
+    #[test]
+    fn fault_location() {
+        let code = "\
+a = #ffffff;
+b = mix( a, c, 50 );
+d = rgb( 1, 2 );
+e = a f";
+        let mut env = Envroiment::new();
+        run(
+            &mut env,
+            code.chars().collect(),
+            app_path::AbsFilePath::create_decoy(),
+        )
+        .unwrap();
+
+        let reports: Vec<String> = env.faults.iter().map(|fault| fault.report()).collect();
+        assert_eq!(
+            reports,
+            vec![
+                "/home/hello:2:13: EvalError: c is Not Found",
+                "/home/hello:3:5: EvalError: Wrong number of arguments. req=3 got=2",
+                "/home/hello:4:7: ParseError: unexpected identifier `f` after the end of the statement",
+            ]
+        );
+    }
+
+    #[test]
+    fn doc_comment() {
+        let code = "\
+/// Editor background
+bg = #101010;
+/* accent
+   /* nested */ */
+/// Primary accent
+palette accent = #3366cc;
+fg = #eeeeee;
+/// dangling
+";
+        let mut env = Envroiment::new();
+        run(
+            &mut env,
+            code.chars().collect(),
+            app_path::AbsFilePath::create_decoy(),
+        )
+        .unwrap();
+        assert!(env.faults.is_empty());
+        assert_eq!(env.get_doc("bg"), Some("Editor background"));
+        assert_eq!(env.get_doc("accent.500"), Some("Primary accent"));
+        assert_eq!(env.get_doc("fg"), None);
+
+        test_run_(
+            "a = #ffffff; /* b = #000000;",
+            vec![("a", Color::new(255, 255, 255))],
+            vec![Box::new(LexFault::UnterminatedComment)],
+        );
+    }
 }
@@ -0,0 +1,566 @@
+// `{{ name }}`を変数の値で置き換えるテンプレート
+// defiroが対応していない設定ファイル(i3、polybar、dunst、rofiなど)を作るために使う
+//
+// {{ name }}               色は`#rrggbb`、文字列はそのまま
+// {{ name | rgb }}         `rgb(29, 31, 33)`
+// {{ name | hex_no_hash }} `1d1f21`
+// {{ for c in vars }}      すべての変数 `colors`なら色の変数だけ
+//   {{ c.name }} = {{ c }}
+// {{ end }}
+//
+// forとendだけの行は行ごと取り除く
+
+use crate::{
+    emit::Variable,
+    fault::Fault,
+    location::{Position, Span, Spanned},
+    utils::{closest_name, round_for_display},
+    value::Value,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum TemplateFault {
+    // `}}`がない
+    Unclosed,
+    Syntax {
+        tag: String,
+    },
+    NotFound {
+        name: String,
+        suggestion: Option<String>,
+    },
+    UnknownFilter {
+        filter: String,
+    },
+    FilterType {
+        filter: &'static str,
+        got: String,
+    },
+    // `{{ for c in palette }}`
+    UnknownList {
+        list: String,
+    },
+    UnclosedLoop,
+    UnexpectedEnd,
+}
+
+impl Fault for TemplateFault {
+    fn msg(&self) -> String {
+        match self {
+            TemplateFault::Unclosed => "TemplateError: Unclosed `{{`".to_string(),
+            TemplateFault::Syntax { tag } => {
+                format!("TemplateError: Cannot understand `{{{{ {} }}}}`", tag)
+            }
+            TemplateFault::NotFound { name, .. } => {
+                format!("TemplateError: `{}` is not defined", name)
+            }
+            TemplateFault::UnknownFilter { filter } => {
+                format!("TemplateError: Unknown filter `{}`", filter)
+            }
+            TemplateFault::FilterType { filter, got } => format!(
+                "TemplateError: `{}` filter requires a color, found {}",
+                filter, got
+            ),
+            TemplateFault::UnknownList { list } => {
+                format!("TemplateError: Cannot loop over `{}`", list)
+            }
+            TemplateFault::UnclosedLoop => {
+                "TemplateError: `{{ for }}` without `{{ end }}`".to_string()
+            }
+            TemplateFault::UnexpectedEnd => {
+                "TemplateError: `{{ end }}` without `{{ for }}`".to_string()
+            }
+        }
+    }
+
+    fn label(&self) -> Option<String> {
+        match self {
+            TemplateFault::Unclosed => Some("this tag is never closed".to_string()),
+            TemplateFault::UnclosedLoop => Some("this loop is never closed".to_string()),
+            _ => None,
+        }
+    }
+
+    fn help(&self) -> Option<String> {
+        match self {
+            TemplateFault::Syntax { .. } => Some(
+                "expected `{{ name }}`, `{{ name | filter }}`, `{{ for c in vars }}` or `{{ end }}`"
+                    .to_string(),
+            ),
+            TemplateFault::NotFound {
+                suggestion: Some(suggestion),
+                ..
+            } => Some(format!("did you mean `{}`?", suggestion)),
+            TemplateFault::UnknownFilter { filter } => match closest_name(filter, FILTERS.into_iter()) {
+                Some(suggestion) => Some(format!("did you mean `{}`?", suggestion)),
+                None => Some(format!("available filters are {}", FILTERS.join(", "))),
+            },
+            TemplateFault::UnknownList { .. } => {
+                Some("loop over `vars` or `colors` instead".to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, Spanned<TemplateFault>>;
+
+const FILTERS: [&str; 2] = ["rgb", "hex_no_hash"];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Filter {
+    Rgb,
+    HexNoHash,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum List {
+    Vars,
+    // 色の変数だけ
+    Colors,
+}
+
+#[derive(Debug, PartialEq)]
+enum Tag {
+    Value {
+        name: String,
+        filter: Option<Filter>,
+    },
+    For {
+        binding: String,
+        list: List,
+    },
+    End,
+}
+
+#[derive(Debug, PartialEq)]
+enum Node {
+    Text(String),
+    Value {
+        name: String,
+        filter: Option<Filter>,
+        span: Span,
+    },
+    For {
+        binding: String,
+        list: List,
+        body: Vec<Node>,
+    },
+}
+
+pub fn render_template(template: &str, vars: &[Variable]) -> Result<String> {
+    let nodes = parse(template)?;
+    let mut output = String::new();
+    render_nodes(&nodes, vars, &mut Vec::new(), &mut output)?;
+    Ok(output)
+}
+
+// 文字列とタグが交互に並ぶ 最初と最後は文字列
+fn split_tags(template: &str) -> Result<Vec<Spanned<String>>> {
+    let chars: Vec<char> = template.chars().collect();
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut text_start = Position::start();
+    let mut position = Position::start();
+    let mut i = 0;
+    while i < chars.len() {
+        if !(chars[i] == '{' && chars.get(i + 1) == Some(&'{')) {
+            text.push(chars[i]);
+            position.advance(chars[i]);
+            i += 1;
+            continue;
+        }
+
+        let tag_start = position;
+        position.advance('{');
+        position.advance('{');
+        let open_end = position;
+        let mut tag = String::new();
+        i += 2;
+        loop {
+            match (chars.get(i), chars.get(i + 1)) {
+                (Some('}'), Some('}')) => break,
+                (Some(ch), _) => {
+                    tag.push(*ch);
+                    position.advance(*ch);
+                    i += 1;
+                }
+                (None, _) => {
+                    return Err(Spanned::new(
+                        TemplateFault::Unclosed,
+                        Span::new(tag_start, open_end),
+                    ))
+                }
+            }
+        }
+        position.advance('}');
+        position.advance('}');
+        i += 2;
+
+        segments.push(Spanned::new(
+            std::mem::take(&mut text),
+            Span::new(text_start, tag_start),
+        ));
+        segments.push(Spanned::new(
+            tag.trim().to_string(),
+            Span::new(tag_start, position),
+        ));
+        text_start = position;
+    }
+    segments.push(Spanned::new(text, Span::new(text_start, position)));
+    Ok(segments)
+}
+
+// forとendだけの行なら、前後の空白と改行を取り除く
+fn strip_block_lines(segments: &mut [Spanned<String>]) {
+    let is_block = |tag: &str| tag == "end" || tag.starts_with("for ");
+    let mut standalone = Vec::new();
+    for i in (1..segments.len()).step_by(2) {
+        let before = &segments[i - 1].inner;
+        let after = &segments[i + 1].inner;
+        let line_start = match before.rfind('\n') {
+            Some(index) => Some(&before[index + 1..]),
+            None if i == 1 => Some(before.as_str()),
+            None => None,
+        };
+        let line_end = match after.find('\n') {
+            Some(index) => Some(&after[..index]),
+            None if i + 2 == segments.len() => Some(after.as_str()),
+            None => None,
+        };
+        let is_blank = |text: Option<&str>| {
+            text.is_some_and(|text| text.chars().all(|ch| ch == ' ' || ch == '\t'))
+        };
+        if is_block(&segments[i].inner) && is_blank(line_start) && is_blank(line_end) {
+            standalone.push(i);
+        }
+    }
+
+    for i in standalone {
+        let before = &mut segments[i - 1].inner;
+        before.truncate(before.rfind('\n').map_or(0, |index| index + 1));
+        let after = &mut segments[i + 1].inner;
+        after.replace_range(
+            ..after.find('\n').map_or(after.len(), |index| index + 1),
+            "",
+        );
+    }
+}
+
+fn parse_tag(tag: &str, span: Span) -> Result<Tag> {
+    let syntax = || {
+        Spanned::new(
+            TemplateFault::Syntax {
+                tag: tag.to_string(),
+            },
+            span,
+        )
+    };
+
+    let words: Vec<&str> = tag.split_whitespace().collect();
+    match words.as_slice() {
+        ["end"] => return Ok(Tag::End),
+        ["for", binding, "in", list] => {
+            let list = match *list {
+                "vars" => List::Vars,
+                "colors" => List::Colors,
+                list => {
+                    return Err(Spanned::new(
+                        TemplateFault::UnknownList {
+                            list: list.to_string(),
+                        },
+                        span,
+                    ))
+                }
+            };
+            return Ok(Tag::For {
+                binding: binding.to_string(),
+                list,
+            });
+        }
+        ["for", ..] => return Err(syntax()),
+        _ => {}
+    }
+
+    let (name, filter) = match tag.split_once('|') {
+        Some((name, filter)) => (name.trim(), Some(filter.trim())),
+        None => (tag, None),
+    };
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(syntax());
+    }
+    let filter = match filter {
+        None => None,
+        Some("rgb") => Some(Filter::Rgb),
+        Some("hex_no_hash") => Some(Filter::HexNoHash),
+        Some(filter) if filter.is_empty() || filter.contains(['|', ' ']) => return Err(syntax()),
+        Some(filter) => {
+            return Err(Spanned::new(
+                TemplateFault::UnknownFilter {
+                    filter: filter.to_string(),
+                },
+                span,
+            ))
+        }
+    };
+    Ok(Tag::Value {
+        name: name.to_string(),
+        filter,
+    })
+}
+
+fn parse(template: &str) -> Result<Vec<Node>> {
+    let mut segments = split_tags(template)?;
+    strip_block_lines(&mut segments);
+
+    // 開いているforと、forの前までのnodes
+    let mut open_loops: Vec<(String, List, Span, Vec<Node>)> = Vec::new();
+    let mut nodes = Vec::new();
+    for (i, segment) in segments.into_iter().enumerate() {
+        if i % 2 == 0 {
+            if !segment.inner.is_empty() {
+                nodes.push(Node::Text(segment.inner));
+            }
+            continue;
+        }
+
+        match parse_tag(&segment.inner, segment.span)? {
+            Tag::Value { name, filter } => nodes.push(Node::Value {
+                name,
+                filter,
+                span: segment.span,
+            }),
+            Tag::For { binding, list } => {
+                open_loops.push((binding, list, segment.span, std::mem::take(&mut nodes)));
+            }
+            Tag::End => {
+                let Some((binding, list, _, outer)) = open_loops.pop() else {
+                    return Err(Spanned::new(TemplateFault::UnexpectedEnd, segment.span));
+                };
+                let body = std::mem::replace(&mut nodes, outer);
+                nodes.push(Node::For {
+                    binding,
+                    list,
+                    body,
+                });
+            }
+        }
+    }
+
+    match open_loops.pop() {
+        Some((_, _, span, _)) => Err(Spanned::new(TemplateFault::UnclosedLoop, span)),
+        None => Ok(nodes),
+    }
+}
+
+fn render_nodes<'a>(
+    nodes: &'a [Node],
+    vars: &'a [Variable<'a>],
+    bindings: &mut Vec<(&'a str, &'a Variable<'a>)>,
+    output: &mut String,
+) -> Result<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Value { name, filter, span } => {
+                let value = lookup(name, vars, bindings)
+                    .ok_or_else(|| Spanned::new(not_found(name, vars, bindings), *span))?;
+                let text =
+                    apply_filter(&value, *filter).map_err(|fault| Spanned::new(fault, *span))?;
+                output.push_str(&text);
+            }
+            Node::For {
+                binding,
+                list,
+                body,
+            } => {
+                for var in vars {
+                    if *list == List::Colors && !matches!(var.value, Value::Color(_)) {
+                        continue;
+                    }
+                    bindings.push((binding, var));
+                    render_nodes(body, vars, bindings, output)?;
+                    bindings.pop();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// ループの変数が優先される `c.name`はループの変数の名前
+fn lookup(name: &str, vars: &[Variable], bindings: &[(&str, &Variable)]) -> Option<Value> {
+    for (binding, var) in bindings.iter().rev() {
+        if name == *binding {
+            return Some(var.value.clone());
+        }
+        if name.strip_prefix(*binding) == Some(".name") {
+            return Some(Value::Str(var.name.to_string()));
+        }
+    }
+    vars.iter()
+        .find(|var| var.name == name)
+        .map(|var| var.value.clone())
+}
+
+fn not_found(name: &str, vars: &[Variable], bindings: &[(&str, &Variable)]) -> TemplateFault {
+    let names: Vec<String> = bindings
+        .iter()
+        .flat_map(|(binding, _)| [binding.to_string(), format!("{}.name", binding)])
+        .chain(vars.iter().map(|var| var.name.to_string()))
+        .collect();
+    TemplateFault::NotFound {
+        name: name.to_string(),
+        suggestion: closest_name(name, names.iter().map(String::as_str)).map(str::to_string),
+    }
+}
+
+fn apply_filter(
+    value: &Value,
+    filter: Option<Filter>,
+) -> std::result::Result<String, TemplateFault> {
+    let (filter, color) = match (filter, value) {
+        (None, value) => return Ok(plain_value(value)),
+        (Some(filter), Value::Color(color)) => (filter, color),
+        (Some(filter), value) => {
+            return Err(TemplateFault::FilterType {
+                filter: match filter {
+                    Filter::Rgb => "rgb",
+                    Filter::HexNoHash => "hex_no_hash",
+                },
+                got: value.describe(),
+            })
+        }
+    };
+
+    Ok(match filter {
+        Filter::HexNoHash => color.to_hex_string()[1..].to_string(),
+        Filter::Rgb if color.is_opaque() => {
+            format!("rgb({}, {}, {})", color.red(), color.green(), color.blue())
+        }
+        Filter::Rgb => format!(
+            "rgba({}, {}, {}, {})",
+            color.red(),
+            color.green(),
+            color.blue(),
+            round_for_display(color.alpha() as f64 / 255.0)
+        ),
+    })
+}
+
+// 文字列は引用符をつけずにそのまま
+fn plain_value(value: &Value) -> String {
+    match value {
+        Value::Color(color) => color.to_hex_string(),
+        Value::Number(number) => number.to_string(),
+        Value::Percent(number) => format!("{}%", number),
+        Value::Str(text) => text.clone(),
+        Value::Palette(palette) => {
+            let colors: Vec<String> = palette.iter().map(|(_, c)| c.to_hex_string()).collect();
+            colors.join(" ")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        color::Color,
+        emit::test::as_variables,
+        location::{Position, Span},
+        value::Value,
+    };
+
+    use super::{render_template, TemplateFault};
+
+    fn values() -> Vec<(&'static str, Value)> {
+        vec![
+            ("bg", Value::Color(Color::new(0x1d, 0x1f, 0x21))),
+            ("fg", Value::Color(Color::new_rgba(0xc5, 0xc8, 0xc6, 0x80))),
+            ("font", Value::Str("Iosevka".to_string())),
+            ("gap", Value::Number(4.0)),
+        ]
+    }
+
+    fn render(template: &str) -> Result<String, TemplateFault> {
+        let values = values();
+        render_template(template, &as_variables(&values, &[])).map_err(|fault| fault.inner)
+    }
+
+    #[test]
+    fn substitute() {
+        assert_eq!(
+            render("bg={{bg}} fg={{ fg }} font={{ font }} gap={{ gap }}px\n"),
+            Ok("bg=#1d1f21 fg=#c5c8c680 font=Iosevka gap=4px\n".to_string())
+        );
+        assert_eq!(
+            render("{{ bg | rgb }} {{ fg|rgb }} {{ bg | hex_no_hash }}"),
+            Ok("rgb(29, 31, 33) rgba(197, 200, 198, 0.5) 1d1f21".to_string())
+        );
+    }
+
+    #[test]
+    fn for_loop() {
+        assert_eq!(
+            render("[colors]\n  {{ for c in colors }}\n{{ c.name }} = {{ c | hex_no_hash }}\n{{ end }}\ndone"),
+            Ok("[colors]\nbg = 1d1f21\nfg = c5c8c680\ndone".to_string())
+        );
+        assert_eq!(
+            render("{{ for v in vars }}{{ v.name }}:{{ v }};{{ end }}"),
+            Ok("bg:#1d1f21;fg:#c5c8c680;font:Iosevka;gap:4;".to_string())
+        );
+        assert_eq!(
+            render("{{ for a in colors }}{{ for b in colors }}{{ a.name }}{{ b.name }} {{ end }}{{ end }}"),
+            Ok("bgbg bgfg fgbg fgfg ".to_string())
+        );
+    }
+
+    #[test]
+    fn faults() {
+        let values = values();
+        let vars = as_variables(&values, &[]);
+        assert_eq!(
+            render_template("a\n  {{ bg", &vars)
+                .err()
+                .map(|fault| fault.span),
+            Some(Span::new(Position::new(2, 3), Position::new(2, 5)))
+        );
+        assert_eq!(
+            render("{{ bgg }}"),
+            Err(TemplateFault::NotFound {
+                name: "bgg".to_string(),
+                suggestion: Some("bg".to_string()),
+            })
+        );
+        assert_eq!(
+            render("{{ gap | rgb }}"),
+            Err(TemplateFault::FilterType {
+                filter: "rgb",
+                got: "number `4`".to_string(),
+            })
+        );
+        assert_eq!(
+            render("{{ bg | hex }}"),
+            Err(TemplateFault::UnknownFilter {
+                filter: "hex".to_string(),
+            })
+        );
+        assert_eq!(
+            render("{{ bg | rgb | hex_no_hash }}"),
+            Err(TemplateFault::Syntax {
+                tag: "bg | rgb | hex_no_hash".to_string(),
+            })
+        );
+        assert_eq!(
+            render("{{ for c in palette }}{{ end }}"),
+            Err(TemplateFault::UnknownList {
+                list: "palette".to_string(),
+            })
+        );
+        assert_eq!(
+            render("{{ for c in vars }}"),
+            Err(TemplateFault::UnclosedLoop)
+        );
+        assert_eq!(render("{{ end }}"), Err(TemplateFault::UnexpectedEnd));
+    }
+}
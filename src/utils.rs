@@ -1,43 +1,64 @@
-use std::collections::VecDeque;
+// JSONの文字列リテラルにする
+pub fn json_string(text: &str) -> String {
+    serde_json::to_string(text).expect("bug")
+}
+
+// エラーメッセージ用に小数第2位までにする
+pub fn round_for_display(number: f64) -> f64 {
+    (number * 100.0).round() / 100.0
+}
 
-pub fn peek_take_while<T>(iter: &mut VecDeque<T>, check: fn(&T) -> bool) -> VecDeque<T> {
-    let mut ret_vec = VecDeque::new();
-    loop {
-        let Some(item) = iter.front() else {
-            break;
-        };
-        if check(item) {
-            break;
+// 挿入、削除、置換を1回と数えた編集距離
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let replace = prev[j] + usize::from(ca != *cb);
+            current.push(replace.min(prev[j + 1] + 1).min(current[j] + 1));
         }
-        let Some(item) = iter.pop_front() else {
-            break;
-        };
-        ret_vec.push_back(item);
+        prev = current;
     }
-    ret_vec
+    prev[b.len()]
+}
+
+// 「もしかして」の候補 長さの3分の1(最低1文字)まで違う名前のうち最も近いもの
+pub fn closest_name<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let limit = (name.chars().count() / 3).max(1);
+    candidates
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min()
+        .map(|(_, candidate)| candidate)
 }
 
 #[cfg(test)]
 mod test {
-    use std::collections::VecDeque;
-
-    use crate::utils::peek_take_while;
+    use super::{closest_name, edit_distance, json_string};
 
     #[test]
-    fn _peek_take_while() {
-        let mut deque = VecDeque::from(vec![1, 2, 3, 4, 5]);
-        let result = peek_take_while(&mut deque, |&x| x == 3);
-        assert_eq!(result, VecDeque::from(vec![1, 2]));
-        assert_eq!(deque, VecDeque::from(vec![3, 4, 5]));
+    fn _edit_distance() {
+        assert_eq!(edit_distance("lighten", "lighten"), 0);
+        assert_eq!(edit_distance("lighen", "lighten"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
 
-        let mut deque: VecDeque<usize> = VecDeque::new();
-        let result = peek_take_while(&mut deque, |&x| x == 3);
-        assert_eq!(result, VecDeque::new());
-        assert_eq!(deque, VecDeque::new());
+    #[test]
+    fn _closest_name() {
+        let names = ["lighten", "darken", "mix", "red"];
+        assert_eq!(closest_name("lihgten", names.into_iter()), Some("lighten"));
+        assert_eq!(closest_name("mx", names.into_iter()), Some("mix"));
+        assert_eq!(closest_name("blue", names.into_iter()), None);
+    }
 
-        let mut deque = VecDeque::from(vec![1, 2, 3, 4, 5]);
-        let result = peek_take_while(&mut deque, |&x| x == 10);
-        assert_eq!(result, VecDeque::from(vec![1, 2, 3, 4, 5]));
-        assert_eq!(deque, VecDeque::new());
+    #[test]
+    fn _json_string() {
+        assert_eq!(json_string("hello"), "\"hello\"");
+        assert_eq!(
+            json_string("a \"b\"\\\n\u{1}"),
+            "\"a \\\"b\\\"\\\\\\n\\u0001\""
+        );
     }
 }
@@ -0,0 +1,34 @@
+use crate::{color::Color, utils::round_for_display};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Color(Color),
+    Number(f64),
+    // 0から100 `50%`は50
+    Percent(f64),
+    Str(String),
+    Palette(Vec<(u32, Color)>),
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Color(_) => "color",
+            Value::Number(_) => "number",
+            Value::Percent(_) => "percentage",
+            Value::Str(_) => "string",
+            Value::Palette(_) => "palette",
+        }
+    }
+
+    // エラーメッセージ用 値も含める
+    pub fn describe(&self) -> String {
+        match self {
+            Value::Color(color) => format!("color `{}`", color.to_hex_string()),
+            Value::Number(number) => format!("number `{}`", round_for_display(*number)),
+            Value::Percent(number) => format!("percentage `{}%`", round_for_display(*number)),
+            Value::Str(text) => format!("string `{:?}`", text),
+            Value::Palette(_) => "palette".to_string(),
+        }
+    }
+}
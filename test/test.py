@@ -19,9 +19,20 @@ for test_dir in test_dirs:
     input_file = test_dir + "/input.txt"
     out_file = test_dir + "/out.txt"
     err_file = test_dir + "/err.txt"
+    # args.txtがあればオプションとして渡す
+    args_file = test_dir + "/args.txt"
+    args = ""
+    if os.path.exists(args_file):
+        with open(args_file) as f:
+            args = f.read().strip()
+    # template.txtがあればrenderでテンプレートを埋める
+    template_file = test_dir + "/template.txt"
+    if os.path.exists(template_file):
+        input_file = f"{input_file} {template_file}"
+        args = f"render {args}"
 
     time_sta = time.perf_counter()   
-    output = subprocess.run(f"{cmd} {input_file}",shell=True,stdout=subprocess.PIPE,stderr=subprocess.PIPE)
+    output = subprocess.run(f"{cmd} {args} {input_file}",shell=True,stdout=subprocess.PIPE,stderr=subprocess.PIPE)
     output_stdout = output.stdout.decode()
     output_stderr = output.stderr.decode()
 